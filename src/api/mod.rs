@@ -74,6 +74,54 @@ pub struct Station {
     pub page: Page,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PlacesResponse {
+    pub data: PlacesData,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PlacesData {
+    pub list: Vec<Place>,
+}
+
+/// A city or town on the globe with one or more stations.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Place {
+    pub id: String,
+    pub title: String,
+    pub country: String,
+    /// Longitude and latitude.
+    pub geo: [f64; 2],
+    /// Number of stations.
+    pub size: u32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ChannelResponse {
+    pub data: Channel,
+}
+
+/// A station's details, including where it broadcasts from.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Channel {
+    pub id: String,
+    pub title: String,
+    pub place: PlaceRef,
+    pub country: Country,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PlaceRef {
+    pub id: String,
+    pub title: String,
+}
+
+/// Radio Garden's redirecting stream endpoint for a station.
+pub fn stream_url(station_id: &str) -> String {
+    format!("{}/ara/content/listen/{}/channel.mp3", BASE_URL, station_id)
+}
+
+#[derive(Clone)]
 pub struct RadioClient {
     client: reqwest::Client,
 }
@@ -99,6 +147,30 @@ impl RadioClient {
         Ok(result)
     }
 
+    /// Every place with stations, for the map.
+    pub async fn get_places(&self) -> Result<PlacesResponse> {
+        let url = format!("{}/ara/content/places", BASE_URL);
+        let response = self.client.get(&url).send().await?;
+        let result = response.json::<PlacesResponse>().await?;
+        Ok(result)
+    }
+
+    /// A place's stations, in the same shape as a country's.
+    pub async fn get_place_stations(&self, place_id: &str) -> Result<CountryPage> {
+        let url = format!("{}/ara/content/secure/page/{}/channels", BASE_URL, place_id);
+        let response = self.client.get(&url).send().await?;
+        let result = response.json::<CountryPage>().await?;
+        Ok(result)
+    }
+
+    pub async fn get_channel(&self, station_id: &str) -> Result<ChannelResponse> {
+        let url = format!("{}/ara/content/channel/{}", BASE_URL, station_id);
+        let response = self.client.get(&url).send().await?;
+        let result = response.json::<ChannelResponse>().await?;
+        Ok(result)
+    }
+
+    #[allow(dead_code)]
     pub async fn get_actual_stream_url(&self, station_id: &str) -> Result<String> {
         let url = format!("{}/ara/content/listen/{}/channel.mp3", BASE_URL, station_id);
         let response = self.client.head(&url).send().await?;
@@ -106,7 +178,7 @@ impl RadioClient {
     }
 
     pub fn get_stream_url(&self, station_id: &str) -> String {
-        format!("{}/ara/content/listen/{}/channel.mp3", BASE_URL, station_id)
+        stream_url(station_id)
     }
 }
 
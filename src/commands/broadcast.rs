@@ -0,0 +1,31 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::daemon::{self, DaemonOptions};
+use crate::icecast::IcecastOptions;
+use crate::player::StationInfo;
+
+pub async fn run(
+    server: &str,
+    user: String,
+    password: String,
+    station: Option<String>,
+    socket: Option<PathBuf>,
+    verbose: bool,
+) -> Result<()> {
+    let socket = socket.unwrap_or_else(daemon::default_socket_path);
+    let icecast = IcecastOptions::new(server, user, password)?;
+    if verbose {
+        println!("Broadcasting to {} with control socket {}", icecast.mount, socket.display());
+    }
+    if station.is_none() {
+        println!("💡 Nothing is playing yet; tune in with `termadio ctl play <id>` or `termadio radio --attach`");
+    }
+
+    let options = DaemonOptions {
+        icecast: Some(icecast),
+        station: station.map(|id| StationInfo::resolve(id, None, None)),
+        ..Default::default()
+    };
+    daemon::serve(&socket, options).await
+}
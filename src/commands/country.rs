@@ -18,7 +18,7 @@ pub async fn run(country_id: &str, verbose: bool) -> Result<()> {
                 println!("🎵 {}", station.page.title);
                 
                 // Extract station ID from URL like "/listen/station-name/stationId"
-                if let Some(station_id) = station.page.url.split('/').last() {
+                if let Some(station_id) = station.page.url.split('/').next_back() {
                     let stream_url = client.get_stream_url(station_id);
                     println!("   Stream: {}", stream_url);
                     if verbose {
@@ -0,0 +1,82 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::daemon::{self, client::DaemonClient};
+use crate::player::{PlaybackState, PlayerEvent, StationInfo};
+
+fn client(socket: Option<PathBuf>) -> DaemonClient {
+    DaemonClient::new(socket.unwrap_or_else(daemon::default_socket_path))
+}
+
+pub fn play(socket: Option<PathBuf>, id: &str) -> Result<()> {
+    let client = client(socket);
+    // The daemon fills in the title from favorites when it knows the station.
+    client.request(&daemon::protocol::Request::Play {
+        id: id.to_string(),
+        title: None,
+        country: None,
+    })?;
+    let status = client.status()?;
+    if let Some(station) = status.station {
+        println!("▶️  Playing: {}", station.title);
+    }
+    Ok(())
+}
+
+pub fn pause(socket: Option<PathBuf>) -> Result<()> {
+    client(socket).pause()?;
+    println!("⏸️  Paused");
+    Ok(())
+}
+
+pub fn resume(socket: Option<PathBuf>) -> Result<()> {
+    client(socket).resume()?;
+    println!("▶️  Resumed");
+    Ok(())
+}
+
+pub fn stop(socket: Option<PathBuf>) -> Result<()> {
+    client(socket).stop()?;
+    println!("⏹️  Stopped");
+    Ok(())
+}
+
+pub fn volume(socket: Option<PathBuf>, level: u8) -> Result<()> {
+    client(socket).set_volume(level)?;
+    println!("🔊 Volume: {}%", level.min(100));
+    Ok(())
+}
+
+pub fn status(socket: Option<PathBuf>) -> Result<()> {
+    let status = client(socket).status()?;
+    let state = match status.state {
+        PlaybackState::Playing => "▶️  Playing",
+        PlaybackState::Paused => "⏸️  Paused",
+        PlaybackState::Stopped => "⏹️  Stopped",
+    };
+    println!("{}", state);
+    if let Some(station) = status.station {
+        print_station(&station);
+    }
+    println!("🔊 Volume: {}%", status.volume);
+    Ok(())
+}
+
+pub fn events(socket: Option<PathBuf>) -> Result<()> {
+    let (status, events) = client(socket).subscribe()?;
+    println!("{}", serde_json::to_string(&status)?);
+    for event in events {
+        let event: PlayerEvent = event?;
+        println!("{}", serde_json::to_string(&event)?);
+    }
+    Ok(())
+}
+
+fn print_station(station: &StationInfo) {
+    let country_info = station
+        .country
+        .as_ref()
+        .map(|c| format!(" - {}", c))
+        .unwrap_or_default();
+    println!("📻 {}{} (ID: {})", station.title, country_info, station.id);
+}
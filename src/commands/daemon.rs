@@ -0,0 +1,36 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::daemon::{self, DaemonOptions};
+use crate::http::HttpOptions;
+
+pub async fn run(
+    socket: Option<PathBuf>,
+    http: bool,
+    relay: bool,
+    bind: Option<String>,
+    verbose: bool,
+) -> Result<()> {
+    let socket = socket.unwrap_or_else(daemon::default_socket_path);
+    if verbose {
+        println!("Starting daemon on socket: {}", socket.display());
+    }
+
+    let http = if http || relay {
+        let mut options = HttpOptions::from_config(&mut Config::load()?)?;
+        if let Some(bind) = bind {
+            options.bind = bind.parse()?;
+        }
+        options.relay |= relay;
+        Some(options)
+    } else {
+        None
+    };
+
+    let options = DaemonOptions {
+        http,
+        ..Default::default()
+    };
+    daemon::serve(&socket, options).await
+}
@@ -6,6 +6,8 @@ pub fn add_country(id: &str, name: &str) -> Result<()> {
     let country = FavoriteCountry {
         id: id.to_string(),
         title: name.to_string(),
+        name: None,
+        note: None,
     };
     favorites.add_country(country)?;
     println!("✅ Added '{}' to favorite countries", name);
@@ -18,6 +20,9 @@ pub fn add_station(id: &str, name: &str, country: Option<&str>) -> Result<()> {
         id: id.to_string(),
         title: name.to_string(),
         country: country.map(|c| c.to_string()),
+        name: None,
+        note: None,
+        fallbacks: Vec::new(),
     };
     favorites.add_station(station)?;
     println!("✅ Added '{}' to favorite stations", name);
@@ -44,6 +49,9 @@ pub fn list() -> Result<()> {
                 .map(|c| format!(" - {}", c))
                 .unwrap_or_default();
             println!("  • {}{} (ID: {})", station.title, country_info, station.id);
+            for fallback in &station.fallbacks {
+                println!("      ↳ fallback: {}", fallback);
+            }
         }
     }
     
@@ -66,4 +74,22 @@ pub fn remove_station(id: &str) -> Result<()> {
     favorites.remove_station(id)?;
     println!("🗑️  Removed station from favorites");
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn add_fallback(id: &str, stream: &str) -> Result<()> {
+    let mut favorites = Favorites::load()?;
+    if !favorites.add_fallback(id, stream)? {
+        anyhow::bail!("Station {} is not a favorite; add it with 'termadio favorites add-station' first", id);
+    }
+    println!("🔀 Added fallback '{}' to station {}", stream, id);
+    Ok(())
+}
+
+pub fn clear_fallbacks(id: &str) -> Result<()> {
+    let mut favorites = Favorites::load()?;
+    if !favorites.clear_fallbacks(id)? {
+        anyhow::bail!("Station {} is not a favorite", id);
+    }
+    println!("🗑️  Removed fallbacks from station {}", id);
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use anyhow::Result;
+use chrono::Local;
+use clap::ValueEnum;
+
+use crate::history::{self, format_duration, History};
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+pub fn run(since: Option<&str>, format: OutputFormat) -> Result<()> {
+    let cutoff = since.map(history::parse_since).transpose()?;
+    let history = History::load()?;
+    let entries: Vec<_> = history.since(cutoff).collect();
+
+    if let OutputFormat::Json = format {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    println!("🕘 Listening History:");
+    println!("━━━━━━━━━━━━━━━━━━━━━");
+
+    if entries.is_empty() {
+        println!("Nothing played yet{}.", since.map(|s| format!(" in the last {}", s)).unwrap_or_default());
+        return Ok(());
+    }
+
+    for entry in entries {
+        let country_info = entry.station.country.as_ref()
+            .map(|c| format!(" - {}", c))
+            .unwrap_or_default();
+        println!(
+            "\n{}  📻 {}{} ({}, ID: {})",
+            entry.started.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            entry.station.title,
+            country_info,
+            format_duration(entry.duration()),
+            entry.station.id
+        );
+        for track in &entry.tracks {
+            println!("    ♪ {}  {}", track.at.with_timezone(&Local).format("%H:%M"), track.title);
+        }
+    }
+
+    Ok(())
+}
@@ -4,3 +4,9 @@ pub mod search;
 pub mod country;
 pub mod radio;
 pub mod favorites;
+pub mod daemon;
+pub mod ctl;
+pub mod web;
+pub mod broadcast;
+pub mod history;
+pub mod stats;
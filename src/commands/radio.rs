@@ -1,7 +1,45 @@
 use anyhow::Result;
-use crate::ui::App;
+use std::path::PathBuf;
+use std::sync::Arc;
 
-pub async fn run() -> Result<()> {
-    let mut app = App::new()?;
+use crate::config::Config;
+use crate::daemon::{self, client::DaemonClient};
+use crate::http::{self, HttpOptions};
+use crate::listenbrainz::{self, ListenBrainzOptions};
+use crate::player::watchdog::{self, DeadAirOptions};
+use crate::player::AudioPlayer;
+use crate::ui::{App, Playback};
+
+pub async fn run(attach: bool, socket: Option<PathBuf>) -> Result<()> {
+    let playback = if attach {
+        let client = DaemonClient::new(socket.unwrap_or_else(daemon::default_socket_path));
+        // Fail before taking over the terminal if the daemon isn't there.
+        client.status()?;
+        Playback::Daemon(client)
+    } else {
+        let player = Arc::new(AudioPlayer::new()?);
+        // Media keys are a nicety; without a session bus the TUI works as before.
+        #[cfg(target_os = "linux")]
+        tokio::spawn(crate::mpris::serve(Arc::clone(&player)));
+
+        tokio::spawn(crate::history::record(Arc::clone(&player)));
+
+        let mut config = Config::load()?;
+        if let Some(options) = DeadAirOptions::from_config(&config.dead_air) {
+            tokio::spawn(watchdog::watch(Arc::clone(&player), options));
+        }
+        if let Some(options) = ListenBrainzOptions::from_config(&config.listenbrainz) {
+            tokio::spawn(listenbrainz::serve(Arc::clone(&player), options));
+        }
+        if config.http.enabled {
+            let options = HttpOptions::from_config(&mut config)?;
+            // Bound up front so a taken port is reported before the TUI takes the screen.
+            let listener = http::bind(&options).await?;
+            tokio::spawn(http::serve(listener, Arc::clone(&player), options));
+        }
+        Playback::Local(player)
+    };
+
+    let mut app = App::new(playback).await?;
     app.run().await
-}
\ No newline at end of file
+}
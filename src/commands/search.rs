@@ -27,7 +27,7 @@ pub async fn run(query: &str, verbose: bool) -> Result<()> {
                 println!("📻 Station: {}", source.title);
                 if let Some(page) = source.page {
                     // Extract station ID from URL like "/listen/station-name/stationId"
-                    if let Some(station_id) = page.url.split('/').last() {
+                    if let Some(station_id) = page.url.split('/').next_back() {
                         println!("   Stream: {}", client.get_stream_url(station_id));
                     }
                 }
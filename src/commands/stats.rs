@@ -0,0 +1,70 @@
+use anyhow::Result;
+use chrono::Duration;
+
+use super::history::OutputFormat;
+use crate::history::format_duration;
+use crate::stats::{Period, Stats, WEEKDAYS};
+
+const BAR_WIDTH: i64 = 30;
+
+pub fn run(period: Period, format: OutputFormat) -> Result<()> {
+    let stats = Stats::load(period)?;
+
+    if let OutputFormat::Json = format {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+
+    println!("📊 Listening Stats ({}):", period.label());
+    println!("━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+
+    if stats.sessions == 0 {
+        println!("Nothing played {}.", if period == Period::All { "yet" } else { "in this period" });
+        return Ok(());
+    }
+
+    println!("Total: {} in {} sessions", minutes(stats.total_minutes), stats.sessions);
+
+    println!("\n📻 Top stations:");
+    for (rank, station) in stats.top_stations.iter().enumerate() {
+        println!("  {:>2}. {} ({})", rank + 1, station.name, minutes(station.minutes));
+    }
+
+    if !stats.top_countries.is_empty() {
+        println!("\n🌍 Top countries:");
+        for (rank, country) in stats.top_countries.iter().enumerate() {
+            println!("  {:>2}. {} ({})", rank + 1, country.name, minutes(country.minutes));
+        }
+    }
+
+    if !stats.top_tracks.is_empty() {
+        println!("\n🎵 Most heard tracks:");
+        for (rank, track) in stats.top_tracks.iter().enumerate() {
+            println!("  {:>2}. {} ({}×)", rank + 1, track.title, track.plays);
+        }
+    }
+
+    println!("\n🕘 By hour of day:");
+    let busiest = stats.by_hour.iter().copied().max().unwrap_or(0);
+    for (hour, &total) in stats.by_hour.iter().enumerate() {
+        println!("  {:02}:00 {} {}", hour, bar(total, busiest), minutes(total));
+    }
+
+    println!("\n📅 By weekday:");
+    let busiest = stats.by_weekday.iter().copied().max().unwrap_or(0);
+    for (day, &total) in WEEKDAYS.iter().zip(stats.by_weekday.iter()) {
+        println!("  {}   {} {}", day, bar(total, busiest), minutes(total));
+    }
+
+    Ok(())
+}
+
+fn minutes(total: i64) -> String {
+    format_duration(Duration::minutes(total))
+}
+
+/// A bar scaled against the busiest bucket, padded so the labels line up.
+fn bar(value: i64, max: i64) -> String {
+    let filled = if max > 0 { value * BAR_WIDTH / max } else { 0 };
+    format!("{:<width$}", "█".repeat(filled as usize), width = BAR_WIDTH as usize)
+}
@@ -0,0 +1,33 @@
+use anyhow::Result;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::daemon::{self, DaemonOptions};
+use crate::http::HttpOptions;
+
+pub async fn run(
+    port: u16,
+    host: Option<IpAddr>,
+    socket: Option<PathBuf>,
+    relay: bool,
+    verbose: bool,
+) -> Result<()> {
+    let socket = socket.unwrap_or_else(daemon::default_socket_path);
+    let mut options = HttpOptions::from_config(&mut Config::load()?)?;
+    let host = host.unwrap_or_else(|| options.bind.ip());
+    options.bind = SocketAddr::new(host, port);
+    options.web_ui = true;
+    options.relay |= relay;
+
+    if verbose {
+        println!("Starting web UI on {} with control socket {}", options.bind, socket.display());
+    }
+    println!("🌍 Open http://{}/?token={}", options.bind, options.token);
+
+    let options = DaemonOptions {
+        http: Some(options),
+        ..Default::default()
+    };
+    daemon::serve(&socket, options).await
+}
@@ -1,13 +1,21 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub app_name: String,
     pub version: String,
     pub debug: bool,
+    pub http: HttpConfig,
+    pub listenbrainz: ListenBrainzConfig,
+    pub dead_air: DeadAirConfig,
+    pub keymap: KeymapConfig,
+    pub theme: ThemeConfig,
+    pub layout: LayoutConfig,
 }
 
 impl Default for Config {
@@ -16,12 +24,158 @@ impl Default for Config {
             app_name: "termadio".to_string(),
             version: "0.1.0".to_string(),
             debug: false,
+            http: HttpConfig::default(),
+            listenbrainz: ListenBrainzConfig::default(),
+            dead_air: DeadAirConfig::default(),
+            keymap: KeymapConfig::default(),
+            theme: ThemeConfig::default(),
+            layout: LayoutConfig::default(),
+        }
+    }
+}
+
+/// Embedded remote-control server; see `crate::http`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Start the server alongside the TUI.
+    pub enabled: bool,
+    pub bind: String,
+    /// Generated and saved on first use when missing.
+    pub token: Option<String>,
+    /// Serve the browser UI from `/` as well as the API.
+    pub web_ui: bool,
+    /// Re-broadcast the playing stream to local HTTP clients at `/stream`.
+    pub relay: bool,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1:7878".to_string(),
+            token: None,
+            web_ui: false,
+            relay: false,
+        }
+    }
+}
+
+/// Scrobbling of ICY track titles; see `crate::listenbrainz`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListenBrainzConfig {
+    pub enabled: bool,
+    /// API root, so a self-hosted server or a local stand-in can be used.
+    pub url: String,
+    /// User token from the ListenBrainz settings page.
+    pub token: Option<String>,
+    /// How long a track has to play before it counts as a listen.
+    pub min_listen_secs: u64,
+}
+
+impl Default for ListenBrainzConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: "https://api.listenbrainz.org".to_string(),
+            token: None,
+            min_listen_secs: 60,
+        }
+    }
+}
+
+/// Silence and stalled-stream detection; see `crate::player::watchdog`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeadAirConfig {
+    pub enabled: bool,
+    /// Seconds of audio below `threshold_db` before a station counts as silent.
+    pub silence_secs: u64,
+    /// Seconds without any data from the station before it counts as stalled.
+    pub stall_secs: u64,
+    /// Level (dBFS RMS) under which audio counts as silence.
+    pub threshold_db: f32,
+    /// Switch to the next favorite station instead of only reporting it.
+    pub auto_skip: bool,
+}
+
+impl Default for DeadAirConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            silence_secs: 30,
+            stall_secs: 15,
+            threshold_db: -50.0,
+            auto_skip: false,
+        }
+    }
+}
+
+/// TUI key bindings; see `crate::ui::keymap`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeymapConfig {
+    /// `default`, or `vim` to add j/k/g/G/h/l navigation.
+    pub preset: String,
+    /// Bindings on top of the preset, e.g. `"ctrl+n": "down"`. Binding a key
+    /// to `none` removes it.
+    pub bindings: BTreeMap<String, String>,
+}
+
+impl Default for KeymapConfig {
+    fn default() -> Self {
+        Self {
+            preset: "default".to_string(),
+            bindings: BTreeMap::new(),
+        }
+    }
+}
+
+/// TUI colors and icons; see `crate::ui::theme`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    /// `dark`, `light`, `high-contrast` or `monochrome`.
+    pub name: String,
+    /// Plain ASCII instead of emoji, box drawing and block characters.
+    pub ascii: bool,
+    /// Colors on top of the theme, e.g. `"accent": "magenta"` or `"#ff8800"`.
+    pub colors: BTreeMap<String, String>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            name: "dark".to_string(),
+            ascii: false,
+            colors: BTreeMap::new(),
+        }
+    }
+}
+
+/// TUI panes; see `crate::ui`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    /// Show favorites in a sidebar next to the main pane.
+    pub sidebar: bool,
+    pub sidebar_width: u16,
+    /// Terminals narrower than this get the single-pane layout.
+    pub min_width: u16,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            sidebar: true,
+            sidebar_width: 32,
+            min_width: 100,
         }
     }
 }
 
 impl Config {
-    #[allow(dead_code)]
     pub fn load() -> Result<Self> {
         let config_path = Self::config_path()?;
 
@@ -34,7 +188,6 @@ impl Config {
         }
     }
 
-    #[allow(dead_code)]
     pub fn save(&self) -> Result<()> {
         let config_path = Self::config_path()?;
 
@@ -48,7 +201,6 @@ impl Config {
         Ok(())
     }
 
-    #[allow(dead_code)]
     fn config_path() -> Result<PathBuf> {
         let mut path =
             dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not find home directory"))?;
@@ -63,7 +215,6 @@ impl Config {
 pub mod dirs {
     use std::path::PathBuf;
 
-    #[allow(dead_code)]
     pub fn home_dir() -> Option<PathBuf> {
         std::env::var_os("HOME").map(PathBuf::from)
     }
@@ -0,0 +1,114 @@
+use anyhow::{anyhow, Context, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+use super::protocol::{Request, Response};
+use crate::player::{PlayerEvent, PlayerStatus, StationInfo};
+
+/// Blocking client for the daemon's control socket. Each call opens its own
+/// connection, so a client can be kept around while the daemon restarts.
+#[derive(Clone)]
+pub struct DaemonClient {
+    socket: PathBuf,
+}
+
+impl DaemonClient {
+    pub fn new(socket: PathBuf) -> Self {
+        Self { socket }
+    }
+
+    fn connect(&self) -> Result<UnixStream> {
+        UnixStream::connect(&self.socket).with_context(|| {
+            format!(
+                "Could not reach termadio daemon at {} (is `termadio daemon` running?)",
+                self.socket.display()
+            )
+        })
+    }
+
+    pub fn request(&self, request: &Request) -> Result<Response> {
+        let mut stream = self.connect()?;
+        send(&mut stream, request)?;
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line)?;
+        match serde_json::from_str(&line)? {
+            Response::Error { message } => Err(anyhow!(message)),
+            response => Ok(response),
+        }
+    }
+
+    fn expect_ok(&self, request: &Request) -> Result<()> {
+        match self.request(request)? {
+            Response::Ok => Ok(()),
+            other => Err(anyhow!("Unexpected response from daemon: {:?}", other)),
+        }
+    }
+
+    pub fn play(&self, station: StationInfo) -> Result<()> {
+        self.expect_ok(&Request::Play {
+            id: station.id,
+            title: Some(station.title),
+            country: station.country,
+        })
+    }
+
+    pub fn pause(&self) -> Result<()> {
+        self.expect_ok(&Request::Pause)
+    }
+
+    pub fn resume(&self) -> Result<()> {
+        self.expect_ok(&Request::Resume)
+    }
+
+    pub fn stop(&self) -> Result<()> {
+        self.expect_ok(&Request::Stop)
+    }
+
+    pub fn set_volume(&self, volume: u8) -> Result<()> {
+        self.expect_ok(&Request::Volume { volume })
+    }
+
+    pub fn status(&self) -> Result<PlayerStatus> {
+        match self.request(&Request::Status)? {
+            Response::Status { status } => Ok(status),
+            other => Err(anyhow!("Unexpected response from daemon: {:?}", other)),
+        }
+    }
+
+    /// Returns the current status followed by a blocking stream of player events.
+    pub fn subscribe(&self) -> Result<(PlayerStatus, impl Iterator<Item = Result<PlayerEvent>>)> {
+        let mut stream = self.connect()?;
+        send(&mut stream, &Request::Subscribe)?;
+
+        let mut lines = BufReader::new(stream).lines();
+        let status = match lines.next() {
+            Some(line) => match serde_json::from_str(&line?)? {
+                Response::Status { status } => status,
+                Response::Error { message } => return Err(anyhow!(message)),
+                other => return Err(anyhow!("Unexpected response from daemon: {:?}", other)),
+            },
+            None => return Err(anyhow!("Daemon closed the connection")),
+        };
+
+        let events = lines.filter_map(|line| {
+            let parsed = line
+                .map_err(anyhow::Error::from)
+                .and_then(|line| Ok(serde_json::from_str::<Response>(&line)?));
+            match parsed {
+                Ok(Response::Event { event }) => Some(Ok(event)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok((status, events))
+    }
+}
+
+fn send(stream: &mut UnixStream, request: &Request) -> Result<()> {
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    Ok(())
+}
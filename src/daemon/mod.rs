@@ -0,0 +1,198 @@
+use anyhow::{anyhow, Result};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast::error::RecvError;
+
+pub mod client;
+pub mod protocol;
+
+use protocol::{Request, Response};
+use crate::http::{self, HttpOptions};
+use crate::config::Config;
+use crate::icecast::{self, IcecastOptions};
+use crate::listenbrainz::{self, ListenBrainzOptions};
+use crate::player::watchdog::{self, DeadAirOptions};
+use crate::player::{AudioPlayer, StationInfo};
+
+/// `$XDG_RUNTIME_DIR/termadio.sock`, or a per-user socket in the temp dir.
+pub fn default_socket_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => Path::new(&dir).join("termadio.sock"),
+        None => {
+            let user = std::env::var("USER").unwrap_or_else(|_| "default".to_string());
+            std::env::temp_dir().join(format!("termadio-{}.sock", user))
+        }
+    }
+}
+
+/// Optional services the daemon runs next to its control socket.
+#[derive(Default)]
+pub struct DaemonOptions {
+    pub http: Option<HttpOptions>,
+    pub icecast: Option<IcecastOptions>,
+    /// Station to start playing right away.
+    pub station: Option<StationInfo>,
+}
+
+/// Runs the player headless, serving control requests on `socket` (and
+/// whatever `options` enables) until Ctrl+C.
+pub async fn serve(socket: &Path, options: DaemonOptions) -> Result<()> {
+    let listener = bind(socket)?;
+    let player = Arc::new(AudioPlayer::new()?);
+
+    #[cfg(target_os = "linux")]
+    {
+        let player = Arc::clone(&player);
+        tokio::spawn(async move {
+            if let Err(e) = crate::mpris::serve(player).await {
+                eprintln!("MPRIS unavailable: {}", e);
+            }
+        });
+    }
+
+    tokio::spawn(crate::history::record(Arc::clone(&player)));
+    let config = Config::load()?;
+    if let Some(dead_air) = DeadAirOptions::from_config(&config.dead_air) {
+        tokio::spawn(watchdog::watch(Arc::clone(&player), dead_air));
+    }
+    if let Some(listenbrainz) = ListenBrainzOptions::from_config(&config.listenbrainz) {
+        println!("🎶 Scrobbling heard tracks to {}", listenbrainz.url());
+        tokio::spawn(listenbrainz::serve(Arc::clone(&player), listenbrainz));
+    }
+
+    println!("🎧 termadio daemon listening on {}", socket.display());
+
+    if let Some(options) = options.http {
+        let listener = http::bind(&options).await?;
+        println!("🌐 HTTP API on http://{} (token: {})", options.bind, options.token);
+        if options.relay {
+            println!("📡 Relaying the current stream at http://{}/stream", options.bind);
+        }
+        let player = Arc::clone(&player);
+        tokio::spawn(async move {
+            if let Err(e) = http::serve(listener, player, options).await {
+                eprintln!("HTTP server stopped: {}", e);
+            }
+        });
+    }
+
+    if let Some(icecast) = options.icecast {
+        println!("📡 Sending the playing stream to Icecast at {}", icecast.mount);
+        let player = Arc::clone(&player);
+        tokio::spawn(async move {
+            if let Err(e) = icecast::serve(player, icecast).await {
+                eprintln!("Icecast source stopped: {}", e);
+            }
+        });
+    }
+
+    if let Some(station) = options.station {
+        if let Err(e) = player.play_station(station) {
+            eprintln!("❌ Could not start playing: {}", e);
+        }
+    }
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted?;
+                let player = Arc::clone(&player);
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, player).await;
+                });
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    player.stop();
+    let _ = std::fs::remove_file(socket);
+    Ok(())
+}
+
+/// Binds the control socket, clearing out a stale one left by a crashed daemon.
+fn bind(socket: &Path) -> Result<UnixListener> {
+    if socket.exists() {
+        if std::os::unix::net::UnixStream::connect(socket).is_ok() {
+            return Err(anyhow!(
+                "A termadio daemon is already listening on {}",
+                socket.display()
+            ));
+        }
+        std::fs::remove_file(socket)?;
+    }
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(UnixListener::bind(socket)?)
+}
+
+async fn handle_connection(stream: UnixStream, player: Arc<AudioPlayer>) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let request = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                let message = format!("Invalid request: {}", e);
+                write_response(&mut writer, &Response::Error { message }).await?;
+                continue;
+            }
+        };
+
+        let response = match request {
+            Request::Play { id, title, country } => {
+                let station = StationInfo::resolve(id, title, country);
+                match player.play_station(station) {
+                    Ok(()) => Response::Ok,
+                    Err(e) => Response::Error { message: e.to_string() },
+                }
+            }
+            Request::Pause => {
+                player.pause();
+                Response::Ok
+            }
+            Request::Resume => {
+                player.resume();
+                Response::Ok
+            }
+            Request::Stop => {
+                player.stop();
+                Response::Ok
+            }
+            Request::Volume { volume } => {
+                player.set_volume(volume);
+                Response::Ok
+            }
+            Request::Status => Response::Status {
+                status: player.status(),
+            },
+            Request::Subscribe => {
+                // Subscribe before reading the status so no event slips in between.
+                let mut events = player.subscribe();
+                let status = player.status();
+                write_response(&mut writer, &Response::Status { status }).await?;
+                loop {
+                    match events.recv().await {
+                        Ok(event) => write_response(&mut writer, &Response::Event { event }).await?,
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => return Ok(()),
+                    }
+                }
+            }
+        };
+        write_response(&mut writer, &response).await?;
+    }
+
+    Ok(())
+}
+
+async fn write_response(writer: &mut tokio::net::unix::OwnedWriteHalf, response: &Response) -> Result<()> {
+    let mut line = serde_json::to_string(response)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    Ok(())
+}
@@ -0,0 +1,36 @@
+//! Newline-delimited JSON spoken over the daemon's control socket.
+//!
+//! Every request gets exactly one response line, except `subscribe`, which
+//! answers with the current status followed by one `event` line per player
+//! event until the client disconnects.
+
+use serde::{Deserialize, Serialize};
+
+use crate::player::{PlayerEvent, PlayerStatus};
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    Play {
+        id: String,
+        #[serde(default)]
+        title: Option<String>,
+        #[serde(default)]
+        country: Option<String>,
+    },
+    Pause,
+    Resume,
+    Stop,
+    Volume { volume: u8 },
+    Status,
+    Subscribe,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Status { status: PlayerStatus },
+    Event { event: PlayerEvent },
+    Error { message: String },
+}
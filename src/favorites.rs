@@ -1,42 +1,96 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::api;
+
+pub const FAVORITES_FILE: &str = "favorites.json";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FavoriteStation {
     pub id: String,
     pub title: String,
     pub country: Option<String>,
+    /// Shown instead of `title`, which stays as Radio Garden has it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Streams to try, in order, when the station's own stream won't play:
+    /// other Radio Garden station IDs or direct stream URLs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FavoriteCountry {
     pub id: String,
     pub title: String,
+    /// Shown instead of `title`, which stays as Radio Garden has it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl FavoriteStation {
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.title)
+    }
+}
+
+impl FavoriteCountry {
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.title)
+    }
+}
+
+/// A removed favorite and where it was in its list, to put it back.
+#[derive(Debug, Clone)]
+pub enum Removed {
+    Station(usize, FavoriteStation),
+    Country(usize, FavoriteCountry),
+}
+
+impl Removed {
+    pub fn display_name(&self) -> &str {
+        match self {
+            Removed::Station(_, station) => station.display_name(),
+            Removed::Country(_, country) => country.display_name(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Favorites {
     pub stations: Vec<FavoriteStation>,
     pub countries: Vec<FavoriteCountry>,
+    /// Where `save` writes: the file loaded from, or the working directory's.
+    #[serde(skip)]
+    file: Option<PathBuf>,
 }
 
 impl Favorites {
     pub fn load() -> Result<Self> {
-        let path = "favorites.json";
-        if Path::new(path).exists() {
+        Self::load_from(Path::new(FAVORITES_FILE))
+    }
+
+    /// Loads the favorites in `path`, which `save` then writes back to.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let mut favorites = if path.exists() {
             let content = fs::read_to_string(path)?;
-            Ok(serde_json::from_str(&content)?)
+            serde_json::from_str(&content)?
         } else {
-            Ok(Self::default())
-        }
+            Self::default()
+        };
+        favorites.file = Some(path.to_path_buf());
+        Ok(favorites)
     }
 
     pub fn save(&self) -> Result<()> {
         let content = serde_json::to_string_pretty(self)?;
-        fs::write("favorites.json", content)?;
+        fs::write(self.file.as_deref().unwrap_or(Path::new(FAVORITES_FILE)), content)?;
         Ok(())
     }
 
@@ -56,15 +110,293 @@ impl Favorites {
         Ok(())
     }
 
-    pub fn remove_station(&mut self, id: &str) -> Result<()> {
-        self.stations.retain(|s| s.id != id);
+    /// Removes station `id`, returning it for `restore` when it was a favorite.
+    pub fn remove_station(&mut self, id: &str) -> Result<Option<Removed>> {
+        let Some(index) = self.stations.iter().position(|s| s.id == id) else {
+            return Ok(None);
+        };
+        let station = self.stations.remove(index);
         self.save()?;
-        Ok(())
+        Ok(Some(Removed::Station(index, station)))
+    }
+
+    /// Removes country `id`, returning it for `restore` when it was a favorite.
+    pub fn remove_country(&mut self, id: &str) -> Result<Option<Removed>> {
+        let Some(index) = self.countries.iter().position(|c| c.id == id) else {
+            return Ok(None);
+        };
+        let country = self.countries.remove(index);
+        self.save()?;
+        Ok(Some(Removed::Country(index, country)))
+    }
+
+    /// Puts a removed favorite back where it was, unless it was added again since.
+    pub fn restore(&mut self, removed: Removed) -> Result<()> {
+        match removed {
+            Removed::Station(index, station) => {
+                if !self.stations.iter().any(|s| s.id == station.id) {
+                    self.stations.insert(index.min(self.stations.len()), station);
+                }
+            }
+            Removed::Country(index, country) => {
+                if !self.countries.iter().any(|c| c.id == country.id) {
+                    self.countries.insert(index.min(self.countries.len()), country);
+                }
+            }
+        }
+        self.save()
+    }
+
+    /// Moves station `id` one place up (or down) the list. Returns whether it moved.
+    pub fn move_station(&mut self, id: &str, up: bool) -> Result<bool> {
+        let Some(index) = self.stations.iter().position(|s| s.id == id) else {
+            return Ok(false);
+        };
+        if !swap_with_neighbor(&mut self.stations, index, up) {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Moves country `id` one place up (or down) the list. Returns whether it moved.
+    pub fn move_country(&mut self, id: &str, up: bool) -> Result<bool> {
+        let Some(index) = self.countries.iter().position(|c| c.id == id) else {
+            return Ok(false);
+        };
+        if !swap_with_neighbor(&mut self.countries, index, up) {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Names station `id`, or with `None` (or its own title) shows its title again. Returns
+    /// `false` when the station isn't a favorite.
+    pub fn rename_station(&mut self, id: &str, name: Option<String>) -> Result<bool> {
+        let Some(station) = self.stations.iter_mut().find(|s| s.id == id) else {
+            return Ok(false);
+        };
+        station.name = name.filter(|name| *name != station.title);
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Names country `id`, or with `None` (or its own title) shows its title again. Returns
+    /// `false` when the country isn't a favorite.
+    pub fn rename_country(&mut self, id: &str, name: Option<String>) -> Result<bool> {
+        let Some(country) = self.countries.iter_mut().find(|c| c.id == id) else {
+            return Ok(false);
+        };
+        country.name = name.filter(|name| *name != country.title);
+        self.save()?;
+        Ok(true)
     }
 
-    pub fn remove_country(&mut self, id: &str) -> Result<()> {
-        self.countries.retain(|c| c.id != id);
+    /// Sets or, with `None`, clears the note on station `id`. Returns `false`
+    /// when the station isn't a favorite.
+    pub fn set_station_note(&mut self, id: &str, note: Option<String>) -> Result<bool> {
+        let Some(station) = self.stations.iter_mut().find(|s| s.id == id) else {
+            return Ok(false);
+        };
+        station.note = note;
         self.save()?;
+        Ok(true)
+    }
+
+    /// Sets or, with `None`, clears the note on country `id`. Returns `false`
+    /// when the country isn't a favorite.
+    pub fn set_country_note(&mut self, id: &str, note: Option<String>) -> Result<bool> {
+        let Some(country) = self.countries.iter_mut().find(|c| c.id == id) else {
+            return Ok(false);
+        };
+        country.note = note;
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Adds `stream` to the end of a station's fallback chain. Returns `false`
+    /// when the station isn't a favorite.
+    pub fn add_fallback(&mut self, id: &str, stream: &str) -> Result<bool> {
+        let Some(station) = self.stations.iter_mut().find(|s| s.id == id) else {
+            return Ok(false);
+        };
+        if !station.fallbacks.iter().any(|f| f == stream) {
+            station.fallbacks.push(stream.to_string());
+            self.save()?;
+        }
+        Ok(true)
+    }
+
+    /// Empties a station's fallback chain. Returns `false` when the station
+    /// isn't a favorite.
+    pub fn clear_fallbacks(&mut self, id: &str) -> Result<bool> {
+        let Some(station) = self.stations.iter_mut().find(|s| s.id == id) else {
+            return Ok(false);
+        };
+        station.fallbacks.clear();
+        self.save()?;
+        Ok(true)
+    }
+
+    /// The fallback chain of station `id`, empty unless it's a favorite with one.
+    pub fn fallbacks(&self, id: &str) -> Vec<String> {
+        self.stations
+            .iter()
+            .find(|s| s.id == id)
+            .map(|s| s.fallbacks.clone())
+            .unwrap_or_default()
+    }
+
+    /// Writes the favorite stations as an M3U playlist of their stream URLs.
+    pub fn export_m3u(&self, path: &str) -> Result<()> {
+        let mut playlist = String::from("#EXTM3U\n");
+        for station in &self.stations {
+            let title = match &station.country {
+                Some(country) => format!("{} ({})", station.title, country),
+                None => station.title.clone(),
+            };
+            playlist.push_str(&format!("#EXTINF:-1,{}\n{}\n", title, api::stream_url(&station.id)));
+        }
+        fs::write(path, playlist)?;
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// The favorite station after (or before) `current_id`, wrapping around.
+    /// Starts from the first (or last) station when `current_id` isn't a favorite.
+    pub fn station_after(&self, current_id: Option<&str>, forward: bool) -> Option<&FavoriteStation> {
+        let len = self.stations.len();
+        if len == 0 {
+            return None;
+        }
+        let position = current_id.and_then(|id| self.stations.iter().position(|s| s.id == id));
+        let index = match (position, forward) {
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+            (None, true) => 0,
+            (None, false) => len - 1,
+        };
+        self.stations.get(index)
+    }
+}
+
+/// Swaps the item at `index` with the one above (or below) it. Returns
+/// `false` at the end of the list.
+fn swap_with_neighbor<T>(items: &mut [T], index: usize, up: bool) -> bool {
+    let neighbor = if up { index.checked_sub(1) } else { Some(index + 1).filter(|&i| i < items.len()) };
+    match neighbor {
+        Some(neighbor) => {
+            items.swap(index, neighbor);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Favorites saved to a file of their own under the temp dir, with
+    /// stations a, b, c and countries x, y.
+    fn favorites(test: &str) -> (Favorites, PathBuf) {
+        let path = std::env::temp_dir().join(format!("termadio-favorites-{}-{}.json", std::process::id(), test));
+        let _ = fs::remove_file(&path);
+        let mut favorites = Favorites::load_from(&path).unwrap();
+        for id in ["a", "b", "c"] {
+            let station = FavoriteStation { id: id.to_string(), title: format!("Station {}", id), country: None, name: None, note: None, fallbacks: Vec::new() };
+            favorites.add_station(station).unwrap();
+        }
+        for id in ["x", "y"] {
+            favorites.add_country(FavoriteCountry { id: id.to_string(), title: format!("Country {}", id), name: None, note: None }).unwrap();
+        }
+        (favorites, path)
+    }
+
+    fn station_ids(favorites: &Favorites) -> Vec<&str> {
+        favorites.stations.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    fn country_ids(favorites: &Favorites) -> Vec<&str> {
+        favorites.countries.iter().map(|c| c.id.as_str()).collect()
+    }
+
+    #[test]
+    fn moves_stop_at_the_ends_of_the_list() {
+        let (mut favorites, path) = favorites("moves");
+        assert!(!favorites.move_station("a", true).unwrap());
+        assert!(!favorites.move_station("c", false).unwrap());
+        assert!(!favorites.move_station("missing", true).unwrap());
+        assert_eq!(station_ids(&favorites), ["a", "b", "c"]);
+
+        assert!(favorites.move_station("a", false).unwrap());
+        assert_eq!(station_ids(&favorites), ["b", "a", "c"]);
+        assert!(favorites.move_station("c", true).unwrap());
+        assert_eq!(station_ids(&favorites), ["b", "c", "a"]);
+
+        assert!(!favorites.move_country("x", true).unwrap());
+        assert!(!favorites.move_country("y", false).unwrap());
+        assert!(favorites.move_country("y", true).unwrap());
+        assert_eq!(country_ids(&favorites), ["y", "x"]);
+
+        let saved = Favorites::load_from(&path).unwrap();
+        assert_eq!(station_ids(&saved), ["b", "c", "a"]);
+        assert_eq!(country_ids(&saved), ["y", "x"]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn restore_puts_favorites_back_where_they_were() {
+        let (mut favorites, path) = favorites("restore");
+        let removed = favorites.remove_station("b").unwrap().unwrap();
+        assert_eq!(station_ids(&favorites), ["a", "c"]);
+        favorites.restore(removed).unwrap();
+        assert_eq!(station_ids(&favorites), ["a", "b", "c"]);
+
+        let removed = favorites.remove_country("x").unwrap().unwrap();
+        favorites.restore(removed).unwrap();
+        assert_eq!(country_ids(&favorites), ["x", "y"]);
+
+        assert!(favorites.remove_station("missing").unwrap().is_none());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn restore_skips_favorites_added_again() {
+        let (mut favorites, path) = favorites("readded");
+        let removed = favorites.remove_station("c").unwrap().unwrap();
+        favorites.remove_station("a").unwrap();
+        favorites.add_station(FavoriteStation { id: "c".to_string(), title: "Station c".to_string(), country: None, name: None, note: None, fallbacks: Vec::new() }).unwrap();
+        favorites.restore(removed).unwrap();
+        assert_eq!(station_ids(&favorites), ["b", "c"]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn clearing_names_and_notes() {
+        let (mut favorites, path) = favorites("names");
+        assert!(favorites.rename_station("a", Some("Mine".to_string())).unwrap());
+        assert_eq!(favorites.stations[0].display_name(), "Mine");
+        assert!(favorites.rename_station("a", None).unwrap());
+        assert_eq!(favorites.stations[0].name, None);
+        assert!(favorites.rename_station("a", Some("Station a".to_string())).unwrap());
+        assert_eq!(favorites.stations[0].name, None);
+        assert!(!favorites.rename_station("missing", Some("Mine".to_string())).unwrap());
+
+        assert!(favorites.rename_country("x", Some("Home".to_string())).unwrap());
+        assert!(favorites.rename_country("x", Some("Country x".to_string())).unwrap());
+        assert_eq!(favorites.countries[0].display_name(), "Country x");
+
+        assert!(favorites.set_station_note("b", Some("mornings".to_string())).unwrap());
+        assert!(favorites.set_station_note("b", None).unwrap());
+        assert_eq!(favorites.stations[1].note, None);
+        assert!(favorites.set_country_note("y", Some("visit".to_string())).unwrap());
+        assert!(favorites.set_country_note("y", None).unwrap());
+        assert_eq!(favorites.countries[1].note, None);
+
+        let saved = Favorites::load_from(&path).unwrap();
+        assert!(saved.stations.iter().all(|s| s.name.is_none() && s.note.is_none()));
+        assert!(saved.countries.iter().all(|c| c.name.is_none() && c.note.is_none()));
+        fs::remove_file(path).unwrap();
+    }
+}
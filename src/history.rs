@@ -0,0 +1,268 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::player::{AudioPlayer, PlayerEvent, StationInfo};
+
+const HISTORY_FILE: &str = "history.json";
+/// Oldest sessions are dropped past this, to keep the file quick to rewrite.
+const MAX_ENTRIES: usize = 5000;
+/// How often an ongoing session's end time is brought up to date, which
+/// bounds what a crash or power cut can lose.
+const HEARTBEAT: std::time::Duration = std::time::Duration::from_secs(30);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HeardTrack {
+    pub title: String,
+    pub at: DateTime<Utc>,
+}
+
+/// One uninterrupted listening session on a station.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub station: StationInfo,
+    pub started: DateTime<Utc>,
+    pub ended: DateTime<Utc>,
+    #[serde(default)]
+    pub tracks: Vec<HeardTrack>,
+}
+
+impl HistoryEntry {
+    pub fn duration(&self) -> Duration {
+        self.ended - self.started
+    }
+}
+
+/// Listening sessions, oldest first.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct History {
+    pub entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    pub fn load() -> Result<Self> {
+        if Path::new(HISTORY_FILE).exists() {
+            let content = fs::read_to_string(HISTORY_FILE)?;
+            Ok(serde_json::from_str(&content)?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(HISTORY_FILE, content)?;
+        Ok(())
+    }
+
+    /// Sessions that ended after `cutoff` (all of them without one), newest first.
+    pub fn since(&self, cutoff: Option<DateTime<Utc>>) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(move |entry| cutoff.is_none_or(|cutoff| entry.ended >= cutoff))
+    }
+
+    fn session_mut(&mut self, key: &SessionKey) -> Option<&mut HistoryEntry> {
+        self.entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.started == key.started && entry.station.id == key.station_id)
+    }
+}
+
+/// Parses periods like `30m`, `12h`, `7d` or `2w`.
+pub fn parse_period(period: &str) -> Result<Duration> {
+    let period = period.trim();
+    let invalid = || anyhow!("Invalid period '{}' (use e.g. 30m, 12h, 7d or 2w)", period);
+    let unit = period.chars().last().ok_or_else(invalid)?;
+    let amount: i64 = period[..period.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| invalid())?;
+    if amount <= 0 {
+        bail!("Invalid period '{}' (it must be longer than zero)", period);
+    }
+    // Out of range amounts are as invalid as a wrong unit.
+    let duration = match unit {
+        'm' => Duration::try_minutes(amount),
+        'h' => Duration::try_hours(amount),
+        'd' => Duration::try_days(amount),
+        'w' => Duration::try_weeks(amount),
+        _ => None,
+    };
+    duration.ok_or_else(invalid)
+}
+
+/// The time a period (as for `parse_period`) ago.
+pub fn parse_since(period: &str) -> Result<DateTime<Utc>> {
+    Utc::now()
+        .checked_sub_signed(parse_period(period)?)
+        .ok_or_else(|| anyhow!("Invalid period '{}' (use e.g. 30m, 12h, 7d or 2w)", period.trim()))
+}
+
+/// Identifies the session a recorder is writing. Other termadio processes
+/// may be recording into the same file, so indexes aren't stable.
+struct SessionKey {
+    station_id: String,
+    started: DateTime<Utc>,
+}
+
+/// Records what `player` plays into the history file until the player goes away.
+pub async fn record(player: Arc<AudioPlayer>) {
+    let mut events = player.subscribe();
+    let mut heartbeat = tokio::time::interval(HEARTBEAT);
+    let mut session = player.status().station.filter(|_| !player.is_paused()).map(start);
+
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            _ = heartbeat.tick() => {
+                if let Some(key) = &session {
+                    touch(key, None);
+                }
+                continue;
+            }
+        };
+
+        match event {
+            PlayerEvent::Playing { station } => {
+                if let Some(key) = session.take() {
+                    touch(&key, None);
+                }
+                session = Some(start(station));
+            }
+            PlayerEvent::Resumed => {
+                if let Some(station) = player.status().station {
+                    session = Some(start(station));
+                }
+            }
+            PlayerEvent::Paused | PlayerEvent::Stopped => {
+                if let Some(key) = session.take() {
+                    touch(&key, None);
+                }
+            }
+            PlayerEvent::TrackChanged { title } => {
+                if let Some(key) = &session {
+                    touch(key, Some(title));
+                }
+            }
+            PlayerEvent::VolumeChanged { .. }
+            | PlayerEvent::DeadAir { .. }
+            | PlayerEvent::FellBack { .. }
+            | PlayerEvent::Unavailable { .. } => {}
+        }
+    }
+
+    if let Some(key) = session {
+        touch(&key, None);
+    }
+}
+
+fn start(station: StationInfo) -> SessionKey {
+    let now = Utc::now();
+    let key = SessionKey {
+        station_id: station.id.clone(),
+        started: now,
+    };
+    update(|history| {
+        history.entries.push(HistoryEntry {
+            station,
+            started: now,
+            ended: now,
+            tracks: Vec::new(),
+        });
+        let excess = history.entries.len().saturating_sub(MAX_ENTRIES);
+        history.entries.drain(..excess);
+    });
+    key
+}
+
+/// Extends the session to now, noting a newly heard track if given.
+fn touch(key: &SessionKey, track: Option<String>) {
+    let now = Utc::now();
+    update(|history| {
+        if let Some(entry) = history.session_mut(key) {
+            entry.ended = now;
+            if let Some(title) = track {
+                entry.tracks.push(HeardTrack { title, at: now });
+            }
+        }
+    });
+}
+
+/// Applies `change` to the file's current contents. History is best-effort:
+/// the recorder also runs under the TUI, where there's nowhere to report errors.
+fn update(change: impl FnOnce(&mut History)) {
+    if let Ok(mut history) = History::load() {
+        change(&mut history);
+        let _ = history.save();
+    }
+}
+
+/// Short human form of a listening time, like `2h 05m` or `12m`.
+pub fn format_duration(duration: Duration) -> String {
+    if duration <= Duration::zero() {
+        return "0m".to_string();
+    }
+    let minutes = duration.num_minutes();
+    match minutes {
+        0 => "<1m".to_string(),
+        1..=59 => format!("{}m", minutes),
+        _ => format!("{}h {:02}m", minutes / 60, minutes % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_periods() {
+        assert_eq!(parse_period("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_period(" 12h ").unwrap(), Duration::hours(12));
+        assert_eq!(parse_period("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_period("2w").unwrap(), Duration::weeks(2));
+    }
+
+    #[test]
+    fn rejects_bad_periods() {
+        for period in ["", "m", "7", "7y", "seven d", "1.5h"] {
+            let error = parse_period(period).unwrap_err().to_string();
+            assert!(error.starts_with("Invalid period"), "{}: {}", period, error);
+        }
+    }
+
+    #[test]
+    fn rejects_periods_of_zero_or_less() {
+        for period in ["0d", "-7d", "-1m", "+0h"] {
+            let error = parse_period(period).unwrap_err().to_string();
+            assert!(error.contains("longer than zero"), "{}: {}", period, error);
+        }
+        assert!(parse_since("-7d").is_err());
+        assert_eq!(parse_period("+1h").unwrap(), Duration::hours(1));
+    }
+
+    #[test]
+    fn rejects_periods_out_of_range() {
+        assert!(parse_period("99999999999w").is_err());
+        assert!(parse_period(&format!("{}m", i64::MAX)).is_err());
+        // Fits in a duration, but not before now.
+        assert!(parse_period("100000000d").is_ok());
+        assert!(parse_since("100000000d").unwrap_err().to_string().starts_with("Invalid period"));
+    }
+
+    #[test]
+    fn since_is_in_the_past() {
+        let since = parse_since("1h").unwrap();
+        let ago = Utc::now() - since;
+        assert!(ago >= Duration::hours(1) && ago < Duration::hours(1) + Duration::minutes(1));
+    }
+}
@@ -0,0 +1,614 @@
+//! Embedded HTTP server for remote control from phones and scripts.
+//!
+//! Everything under `/api` requires the configured token, either as an
+//! `Authorization: Bearer <token>` header or a `?token=` query parameter
+//! (browsers can't set headers on `EventSource`). With `web_ui` set, the
+//! static single-page UI in `web/` is served from `/` without a token, and
+//! with `relay` set the audio being played is re-broadcast at `/stream`.
+
+use anyhow::{Context, Result};
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, patch, post, put},
+    Json, Router,
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::api::{RadioClient, SearchResult};
+use crate::config::Config;
+use crate::favorites::{FavoriteCountry, FavoriteStation, Favorites, FAVORITES_FILE};
+use crate::player::icy::IcyWriter;
+use crate::player::{AudioPlayer, PlayerStatus, StationInfo, StreamChunk};
+
+/// Metadata interval offered to relay clients that ask for ICY metadata.
+const RELAY_METAINT: usize = 16000;
+
+pub struct HttpOptions {
+    pub bind: SocketAddr,
+    pub token: String,
+    /// Also serve the browser UI.
+    pub web_ui: bool,
+    /// Re-broadcast the current stream at `/stream`.
+    pub relay: bool,
+}
+
+impl HttpOptions {
+    /// Reads the `http` section of the config, generating and saving a token
+    /// the first time so clients keep working across restarts.
+    pub fn from_config(config: &mut Config) -> Result<Self> {
+        let token = match &config.http.token {
+            Some(token) => token.clone(),
+            None => {
+                let token = generate_token()?;
+                config.http.token = Some(token.clone());
+                config.save()?;
+                token
+            }
+        };
+        Ok(Self {
+            bind: config.http.bind.parse()?,
+            token,
+            web_ui: config.http.web_ui,
+            relay: config.http.relay,
+        })
+    }
+}
+
+/// 128 bits from the OS random number generator, as hex.
+fn generate_token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[derive(Clone)]
+struct AppState {
+    player: Arc<AudioPlayer>,
+    client: RadioClient,
+}
+
+/// Listens on the configured address, so a port that's taken fails here
+/// rather than in the background.
+pub async fn bind(options: &HttpOptions) -> Result<TcpListener> {
+    TcpListener::bind(options.bind)
+        .await
+        .with_context(|| format!("Couldn't start the HTTP server on {}", options.bind))
+}
+
+/// Serves the API for `player` on `listener` until the process exits.
+pub async fn serve(listener: TcpListener, player: Arc<AudioPlayer>, options: HttpOptions) -> Result<()> {
+    let state = AppState {
+        player,
+        client: RadioClient::new(),
+    };
+
+    axum::serve(listener, router(state, &options)).await?;
+    Ok(())
+}
+
+fn router(state: AppState, options: &HttpOptions) -> Router {
+    let player = Router::new()
+        .route("/search", get(search))
+        .route("/countries/:id/stations", get(country_stations))
+        .route("/status", get(status))
+        .route("/player/play", post(play))
+        .route("/player/pause", post(pause))
+        .route("/player/resume", post(resume))
+        .route("/player/stop", post(stop))
+        .route("/player/volume", put(volume))
+        .route("/events", get(events))
+        .with_state(state.clone());
+
+    let mut router = api(player, &options.token, PathBuf::from(FAVORITES_FILE));
+    if options.web_ui {
+        router = router
+            .route("/", get(|| asset("text/html; charset=utf-8", include_str!("web/index.html"))))
+            .route("/app.js", get(|| asset("text/javascript; charset=utf-8", include_str!("web/app.js"))))
+            .route("/style.css", get(|| asset("text/css; charset=utf-8", include_str!("web/style.css"))));
+    }
+    if options.relay {
+        // Media players can't send tokens, and the stream is only audio.
+        router = router.route("/stream", get(relay).with_state(state));
+    }
+    router
+}
+
+/// `/api`: the `player` routes and the favorites in `favorites_file`, all
+/// behind the token.
+fn api(player: Router, token: &str, favorites_file: PathBuf) -> Router {
+    let favorites = Router::new()
+        .route("/favorites", get(list_favorites))
+        .route("/favorites/stations", post(add_favorite_station))
+        .route(
+            "/favorites/stations/:id",
+            patch(update_favorite_station).delete(remove_favorite_station),
+        )
+        .route("/favorites/countries", post(add_favorite_country))
+        .route(
+            "/favorites/countries/:id",
+            patch(update_favorite_country).delete(remove_favorite_country),
+        )
+        .with_state(Arc::new(favorites_file));
+
+    let api = player
+        .merge(favorites)
+        .route_layer(middleware::from_fn_with_state(Arc::<str>::from(token), require_token));
+    Router::new().nest("/api", api)
+}
+
+async fn asset(content_type: &'static str, body: &'static str) -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, content_type)], body)
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+async fn require_token(
+    State(token): State<Arc<str>>,
+    Query(query): Query<TokenQuery>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let bearer = request
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let provided = bearer.or(query.token.as_deref());
+
+    // Constant-time, so response times don't give away how much of a guess was right.
+    let valid = provided.is_some_and(|provided| bool::from(provided.as_bytes().ct_eq(token.as_bytes())));
+    if valid {
+        next.run(request).await
+    } else {
+        ApiError::new(StatusCode::UNAUTHORIZED, "Missing or invalid token").into_response()
+    }
+}
+
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+async fn search(
+    State(state): State<AppState>,
+    Query(query): Query<SearchQuery>,
+) -> ApiResult<Json<SearchResult>> {
+    Ok(Json(state.client.search(&query.q).await?))
+}
+
+/// Flattens a country page into the stations it lists.
+async fn country_stations(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Vec<FavoriteStation>>> {
+    let page = state.client.get_country_stations(&id).await?;
+    let stations = page
+        .data
+        .content
+        .into_iter()
+        .flat_map(|content| content.items.unwrap_or_default())
+        .filter_map(|item| {
+            let id = item.page.url.split('/').next_back()?.to_string();
+            Some(FavoriteStation {
+                id,
+                title: item.page.title,
+                country: None,
+                name: None,
+                note: None,
+                fallbacks: Vec::new(),
+            })
+        })
+        .collect();
+    Ok(Json(stations))
+}
+
+async fn list_favorites(State(file): State<Arc<PathBuf>>) -> ApiResult<Json<Favorites>> {
+    Ok(Json(Favorites::load_from(&file)?))
+}
+
+async fn add_favorite_station(
+    State(file): State<Arc<PathBuf>>,
+    Json(station): Json<FavoriteStation>,
+) -> ApiResult<Json<Favorites>> {
+    let mut favorites = Favorites::load_from(&file)?;
+    favorites.add_station(station)?;
+    Ok(Json(favorites))
+}
+
+/// Body of a favorite update; fields left out stay as they are.
+#[derive(Deserialize)]
+struct FavoriteUpdate {
+    /// Name shown instead of the title; empty to show the title again.
+    name: Option<String>,
+    /// Empty to clear it.
+    note: Option<String>,
+    /// One place up or down the list.
+    #[serde(rename = "move")]
+    direction: Option<Direction>,
+}
+
+#[derive(Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Direction {
+    Up,
+    Down,
+}
+
+fn non_empty(value: String) -> Option<String> {
+    Some(value).filter(|value| !value.trim().is_empty())
+}
+
+async fn update_favorite_station(
+    State(file): State<Arc<PathBuf>>,
+    Path(id): Path<String>,
+    Json(update): Json<FavoriteUpdate>,
+) -> ApiResult<Json<Favorites>> {
+    let mut favorites = Favorites::load_from(&file)?;
+    if !favorites.stations.iter().any(|s| s.id == id) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "Not a favorite station"));
+    }
+    if let Some(name) = update.name {
+        favorites.rename_station(&id, non_empty(name))?;
+    }
+    if let Some(note) = update.note {
+        favorites.set_station_note(&id, non_empty(note))?;
+    }
+    if let Some(direction) = update.direction {
+        favorites.move_station(&id, direction == Direction::Up)?;
+    }
+    Ok(Json(favorites))
+}
+
+async fn remove_favorite_station(
+    State(file): State<Arc<PathBuf>>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Favorites>> {
+    let mut favorites = Favorites::load_from(&file)?;
+    favorites.remove_station(&id)?;
+    Ok(Json(favorites))
+}
+
+async fn add_favorite_country(
+    State(file): State<Arc<PathBuf>>,
+    Json(country): Json<FavoriteCountry>,
+) -> ApiResult<Json<Favorites>> {
+    let mut favorites = Favorites::load_from(&file)?;
+    favorites.add_country(country)?;
+    Ok(Json(favorites))
+}
+
+async fn update_favorite_country(
+    State(file): State<Arc<PathBuf>>,
+    Path(id): Path<String>,
+    Json(update): Json<FavoriteUpdate>,
+) -> ApiResult<Json<Favorites>> {
+    let mut favorites = Favorites::load_from(&file)?;
+    if !favorites.countries.iter().any(|c| c.id == id) {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "Not a favorite country"));
+    }
+    if let Some(name) = update.name {
+        favorites.rename_country(&id, non_empty(name))?;
+    }
+    if let Some(note) = update.note {
+        favorites.set_country_note(&id, non_empty(note))?;
+    }
+    if let Some(direction) = update.direction {
+        favorites.move_country(&id, direction == Direction::Up)?;
+    }
+    Ok(Json(favorites))
+}
+
+async fn remove_favorite_country(
+    State(file): State<Arc<PathBuf>>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<Favorites>> {
+    let mut favorites = Favorites::load_from(&file)?;
+    favorites.remove_country(&id)?;
+    Ok(Json(favorites))
+}
+
+async fn status(State(state): State<AppState>) -> Json<PlayerStatus> {
+    Json(state.player.status())
+}
+
+#[derive(Deserialize)]
+struct PlayRequest {
+    id: String,
+    title: Option<String>,
+    country: Option<String>,
+}
+
+async fn play(
+    State(state): State<AppState>,
+    Json(request): Json<PlayRequest>,
+) -> ApiResult<Json<PlayerStatus>> {
+    let station = StationInfo::resolve(request.id, request.title, request.country);
+    state.player.play_station(station)?;
+    Ok(Json(state.player.status()))
+}
+
+async fn pause(State(state): State<AppState>) -> Json<PlayerStatus> {
+    state.player.pause();
+    Json(state.player.status())
+}
+
+async fn resume(State(state): State<AppState>) -> Json<PlayerStatus> {
+    state.player.resume();
+    Json(state.player.status())
+}
+
+async fn stop(State(state): State<AppState>) -> Json<PlayerStatus> {
+    state.player.stop();
+    Json(state.player.status())
+}
+
+#[derive(Deserialize)]
+struct VolumeRequest {
+    volume: u8,
+}
+
+async fn volume(
+    State(state): State<AppState>,
+    Json(request): Json<VolumeRequest>,
+) -> Json<PlayerStatus> {
+    state.player.set_volume(request.volume);
+    Json(state.player.status())
+}
+
+/// Server-Sent Events: the current status as a `status` event, then one
+/// event per player event, named after its `event` field.
+async fn events(State(state): State<AppState>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let receiver = state.player.subscribe();
+    let initial = Event::default()
+        .event("status")
+        .json_data(state.player.status())
+        .ok();
+
+    let updates = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => {
+                    let name = serde_json::to_value(&event)
+                        .ok()
+                        .and_then(|v| v["event"].as_str().map(str::to_string))
+                        .unwrap_or_else(|| "message".to_string());
+                    let Ok(sse) = Event::default().event(name).json_data(&event) else {
+                        continue;
+                    };
+                    return Some((Ok(sse), receiver));
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    let stream = stream::iter(initial.map(Ok)).chain(updates);
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Streams whatever the player is playing, following station changes. The
+/// response ends when the codec changes, so clients reconnect and pick up
+/// the new content type.
+async fn relay(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    let chunks = state.player.subscribe_stream();
+    let content_type = state
+        .player
+        .content_type()
+        .unwrap_or_else(|| "audio/mpeg".to_string());
+    let station = state.player.status().station;
+    let wants_icy = headers
+        .get("icy-metadata")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.trim() == "1");
+
+    let relayed = RelayState {
+        player: Arc::clone(&state.player),
+        chunks,
+        content_type: content_type.clone(),
+        icy: wants_icy.then(|| IcyWriter::new(RELAY_METAINT)),
+    };
+    let body = stream::unfold(relayed, |mut relayed| async move {
+        let bytes = relayed.next_bytes().await?;
+        Some((Ok::<_, Infallible>(bytes), relayed))
+    });
+
+    let mut response = Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .header(header::CACHE_CONTROL, "no-cache, no-store")
+        .header("icy-name", station.map(|s| s.title).unwrap_or_else(|| "termadio".to_string()));
+    if wants_icy {
+        response = response.header("icy-metaint", RELAY_METAINT.to_string());
+    }
+    response
+        .body(Body::from_stream(body))
+        .unwrap_or_else(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())
+}
+
+struct RelayState {
+    player: Arc<AudioPlayer>,
+    chunks: tokio::sync::broadcast::Receiver<StreamChunk>,
+    content_type: String,
+    icy: Option<IcyWriter>,
+}
+
+impl RelayState {
+    /// The next piece of body to send, or `None` to end the response.
+    async fn next_bytes(&mut self) -> Option<Bytes> {
+        loop {
+            match self.chunks.recv().await {
+                Ok(StreamChunk::Audio(audio)) => match &mut self.icy {
+                    Some(icy) => {
+                        let track = self.player.status().track;
+                        let mut out = Vec::with_capacity(audio.len() + 64);
+                        icy.write(&audio, track.as_deref(), &mut out);
+                        return Some(Bytes::from(out));
+                    }
+                    None => return Some(audio),
+                },
+                Ok(StreamChunk::Started { content_type }) => {
+                    if content_type != self.content_type {
+                        return None;
+                    }
+                }
+                // A slow client just skips ahead rather than holding everyone up.
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::to_bytes;
+    use axum::http::Method;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    const TOKEN: &str = "secret";
+
+    /// The API with only the favorites routes, kept in a file of the test's own.
+    fn app(test: &str) -> (Router, PathBuf) {
+        let file = std::env::temp_dir().join(format!("termadio-http-{}-{}.json", std::process::id(), test));
+        let _ = std::fs::remove_file(&file);
+        (api(Router::new(), TOKEN, file.clone()), file)
+    }
+
+    async fn send(app: &Router, method: Method, uri: &str, token: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header("authorization", format!("Bearer {}", token));
+        }
+        let request = match body {
+            Some(body) => request.header(header::CONTENT_TYPE, "application/json").body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        };
+        let response = app.clone().oneshot(request.unwrap()).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    fn station_ids(favorites: &Value) -> Vec<&str> {
+        favorites["stations"].as_array().unwrap().iter().map(|s| s["id"].as_str().unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn requires_the_token() {
+        let (app, _) = app("token");
+        let (status, body) = send(&app, Method::GET, "/api/favorites", None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "Missing or invalid token");
+        let (status, _) = send(&app, Method::GET, "/api/favorites", Some("guess"), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = send(&app, Method::GET, "/api/favorites?token=guess", None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        // Only the bearer scheme is accepted.
+        let request = Request::get("/api/favorites").header("authorization", TOKEN).body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn accepts_the_token_as_a_header_or_query() {
+        let (app, _) = app("token-ok");
+        let (status, body) = send(&app, Method::GET, "/api/favorites", Some(TOKEN), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["stations"], Value::Array(Vec::new()));
+        let (status, _) = send(&app, Method::GET, "/api/favorites?token=secret", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn adds_lists_and_removes_favorites() {
+        let (app, file) = app("favorites");
+        for id in ["a", "b"] {
+            let station = json!({ "id": id, "title": format!("Station {}", id), "country": "France" });
+            let (status, _) = send(&app, Method::POST, "/api/favorites/stations", Some(TOKEN), Some(station)).await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        let country = json!({ "id": "fr", "title": "France" });
+        let (status, _) = send(&app, Method::POST, "/api/favorites/countries", Some(TOKEN), Some(country)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (_, favorites) = send(&app, Method::GET, "/api/favorites", Some(TOKEN), None).await;
+        assert_eq!(station_ids(&favorites), ["a", "b"]);
+        assert_eq!(favorites["countries"][0]["id"], "fr");
+
+        let update = json!({ "name": "Mine", "note": "mornings", "move": "up" });
+        let (status, favorites) = send(&app, Method::PATCH, "/api/favorites/stations/b", Some(TOKEN), Some(update)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(station_ids(&favorites), ["b", "a"]);
+        assert_eq!(favorites["stations"][0]["name"], "Mine");
+        assert_eq!(favorites["stations"][0]["note"], "mornings");
+
+        let (status, favorites) = send(&app, Method::DELETE, "/api/favorites/stations/a", Some(TOKEN), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(station_ids(&favorites), ["b"]);
+        let (_, favorites) = send(&app, Method::DELETE, "/api/favorites/countries/fr", Some(TOKEN), None).await;
+        assert_eq!(favorites["countries"], Value::Array(Vec::new()));
+
+        let (_, favorites) = send(&app, Method::GET, "/api/favorites", Some(TOKEN), None).await;
+        assert_eq!(station_ids(&favorites), ["b"]);
+        std::fs::remove_file(file).unwrap();
+    }
+
+    #[tokio::test]
+    async fn updating_an_unknown_favorite_is_not_found() {
+        let (app, _) = app("unknown");
+        let update = json!({ "name": "Mine" });
+        let (status, body) = send(&app, Method::PATCH, "/api/favorites/stations/nope", Some(TOKEN), Some(update.clone())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "Not a favorite station");
+        let (status, _) = send(&app, Method::PATCH, "/api/favorites/countries/nope", Some(TOKEN), Some(update)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,189 @@
+// termadio web UI: a thin client for the /api endpoints of the same server.
+
+const params = new URLSearchParams(location.search);
+if (params.has("token")) {
+  localStorage.setItem("termadio-token", params.get("token"));
+  history.replaceState(null, "", location.pathname);
+}
+const token = localStorage.getItem("termadio-token") || "";
+
+const $ = (id) => document.getElementById(id);
+let status = { state: "stopped", station: null, track: null, volume: 100 };
+let favorites = { stations: [], countries: [] };
+
+async function api(method, path, body) {
+  const response = await fetch("/api" + path, {
+    method,
+    headers: {
+      "Authorization": "Bearer " + token,
+      ...(body ? { "Content-Type": "application/json" } : {}),
+    },
+    body: body ? JSON.stringify(body) : undefined,
+  });
+  const data = await response.json().catch(() => ({}));
+  if (!response.ok) {
+    showError(data.error || response.statusText);
+    throw new Error(data.error);
+  }
+  return data;
+}
+
+function showError(message) {
+  const el = $("error");
+  el.textContent = message;
+  el.hidden = false;
+  clearTimeout(showError.timer);
+  showError.timer = setTimeout(() => (el.hidden = true), 4000);
+}
+
+const lastSegment = (url) => (url || "").split("/").pop();
+
+function button(label, title, onClick) {
+  const el = document.createElement("button");
+  el.textContent = label;
+  el.title = title;
+  el.addEventListener("click", onClick);
+  return el;
+}
+
+function listItem(text, playing, ...buttons) {
+  const li = document.createElement("li");
+  if (playing) li.classList.add("playing");
+  const span = document.createElement("span");
+  span.className = "title";
+  span.textContent = text;
+  li.append(span, ...buttons);
+  return li;
+}
+
+const isFavoriteStation = (id) => favorites.stations.some((s) => s.id === id);
+const isFavoriteCountry = (id) => favorites.countries.some((c) => c.id === id);
+const isPlaying = (id) => status.station && status.station.id === id;
+
+function play(station) {
+  api("POST", "/player/play", station).then(setStatus);
+}
+
+async function toggleStation(station) {
+  favorites = isFavoriteStation(station.id)
+    ? await api("DELETE", "/favorites/stations/" + encodeURIComponent(station.id))
+    : await api("POST", "/favorites/stations", station);
+  renderFavorites();
+}
+
+async function toggleCountry(country) {
+  favorites = isFavoriteCountry(country.id)
+    ? await api("DELETE", "/favorites/countries/" + encodeURIComponent(country.id))
+    : await api("POST", "/favorites/countries", country);
+  renderFavorites();
+}
+
+function stationItem(station) {
+  return listItem(
+    "📻 " + (station.name || station.title) + (station.country ? " (" + station.country + ")" : ""),
+    isPlaying(station.id),
+    button("▶", "Play", () => play(station)),
+    button(isFavoriteStation(station.id) ? "★" : "☆", "Toggle favorite", () => toggleStation(station)),
+  );
+}
+
+function countryItem(country) {
+  return listItem(
+    "🌍 " + (country.name || country.title),
+    false,
+    button("Stations", "List stations", () => showCountry(country)),
+    button(isFavoriteCountry(country.id) ? "★" : "☆", "Toggle favorite", () => toggleCountry(country)),
+  );
+}
+
+function showResults(title, items) {
+  $("results-title").textContent = title;
+  $("results-title").hidden = false;
+  $("results").replaceChildren(...items);
+}
+
+async function search(query) {
+  const result = await api("GET", "/search?q=" + encodeURIComponent(query));
+  const items = result.hits.hits.map(({ _source: source }) => {
+    if (source.type === "country") {
+      return countryItem({ id: lastSegment(source.url), title: source.title });
+    }
+    if (source.type === "channel" && source.page) {
+      return stationItem({
+        id: lastSegment(source.page.url),
+        title: source.title,
+        country: source.country ? source.country.title : null,
+      });
+    }
+    return listItem("❓ " + source.title, false);
+  });
+  showResults("Results for “" + query + "”", items);
+}
+
+async function showCountry(country) {
+  const stations = await api("GET", "/countries/" + encodeURIComponent(country.id) + "/stations");
+  showResults(country.title, stations.map((s) => stationItem({ ...s, country: country.title })));
+}
+
+function renderFavorites() {
+  $("favorites").replaceChildren(
+    ...favorites.countries.map(countryItem),
+    ...favorites.stations.map(stationItem),
+  );
+}
+
+function setStatus(next) {
+  status = next;
+  const labels = { playing: "▶ Playing", paused: "⏸ Paused", stopped: "⏹ Stopped" };
+  $("np-state").textContent = labels[status.state];
+  $("np-title").textContent = status.station ? status.station.title : "Nothing playing";
+  $("np-track").textContent = status.track || "";
+  $("volume").value = status.volume;
+  $("volume-value").textContent = status.volume + "%";
+  document.title = status.station ? "▶ " + status.station.title + " · termadio" : "termadio";
+  renderFavorites();
+}
+
+function connectEvents() {
+  const events = new EventSource("/api/events?token=" + encodeURIComponent(token));
+  events.onopen = () => ($("connection").textContent = "connected");
+  events.onerror = () => ($("connection").textContent = "reconnecting…");
+  events.addEventListener("status", (e) => setStatus(JSON.parse(e.data)));
+  events.addEventListener("dead_air", (e) => {
+    const { station, reason, skipping } = JSON.parse(e.data);
+    showError(station.title + " seems dead (" + reason + ")" + (skipping ? ", switching to the next favorite" : ""));
+  });
+  events.addEventListener("fell_back", (e) => {
+    const { station, stream } = JSON.parse(e.data);
+    showError(station.title + " is playing from fallback " + stream);
+  });
+  events.addEventListener("unavailable", (e) => {
+    const { station, reason } = JSON.parse(e.data);
+    showError(station.title + " could not be played: " + reason);
+  });
+  // Any player event may change several fields, so just refetch the status.
+  for (const name of ["playing", "paused", "resumed", "stopped", "volume_changed", "track_changed"]) {
+    events.addEventListener(name, () => api("GET", "/status").then(setStatus));
+  }
+}
+
+$("play-pause").addEventListener("click", () => {
+  const action = status.state === "playing" ? "/player/pause" : "/player/resume";
+  api("POST", action).then(setStatus);
+});
+$("stop").addEventListener("click", () => api("POST", "/player/stop").then(setStatus));
+$("volume").addEventListener("input", (e) => ($("volume-value").textContent = e.target.value + "%"));
+$("volume").addEventListener("change", (e) =>
+  api("PUT", "/player/volume", { volume: Number(e.target.value) }).then(setStatus),
+);
+$("search-form").addEventListener("submit", (e) => {
+  e.preventDefault();
+  const query = $("search-input").value.trim();
+  if (query) search(query);
+});
+
+api("GET", "/favorites").then((data) => {
+  favorites = data;
+  renderFavorites();
+});
+connectEvents();
@@ -0,0 +1,492 @@
+//! Submits tracks heard on the radio, as announced in ICY titles, to a
+//! ListenBrainz-compatible API. See https://listenbrainz.readthedocs.io/en/latest/users/api/
+//!
+//! Every finished listen goes through a queue file first, so listens heard
+//! while offline (or while the server is down) are sent once it's reachable.
+
+use anyhow::Result;
+use chrono::Utc;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::ListenBrainzConfig;
+use crate::player::icy::split_artist_title;
+use crate::player::{AudioPlayer, PlayerEvent};
+
+const QUEUE_FILE: &str = "listenbrainz-queue.json";
+const RETRY_INTERVAL: Duration = Duration::from_secs(60);
+/// Listens per import request; the API accepts up to 1000.
+const BATCH_SIZE: usize = 100;
+
+pub struct ListenBrainzOptions {
+    url: String,
+    token: String,
+    min_listen: Duration,
+    /// Where finished listens wait to be sent.
+    queue: PathBuf,
+}
+
+impl ListenBrainzOptions {
+    /// `None` unless scrobbling is enabled and has a token.
+    pub fn from_config(config: &ListenBrainzConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        Some(Self {
+            url: config.url.trim_end_matches('/').to_string(),
+            token: config.token.clone()?,
+            min_listen: Duration::from_secs(config.min_listen_secs),
+            queue: PathBuf::from(QUEUE_FILE),
+        })
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct Listen {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    listened_at: Option<i64>,
+    track_metadata: TrackMetadata,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct TrackMetadata {
+    artist_name: String,
+    track_name: String,
+    additional_info: AdditionalInfo,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct AdditionalInfo {
+    media_player: String,
+    submission_client: String,
+    submission_client_version: String,
+}
+
+impl Listen {
+    /// A listen starting now, if the title follows `Artist - Title`. Other
+    /// titles are usually station jingles or show names, and can't be scrobbled.
+    fn starting_now(title: &str) -> Option<Self> {
+        let (artist, track) = split_artist_title(title);
+        Some(Self {
+            listened_at: Some(Utc::now().timestamp()),
+            track_metadata: TrackMetadata {
+                artist_name: artist?.to_string(),
+                track_name: track.to_string(),
+                additional_info: AdditionalInfo {
+                    media_player: "termadio".to_string(),
+                    submission_client: "termadio".to_string(),
+                    submission_client_version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+            },
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Submission<'a> {
+    listen_type: &'static str,
+    payload: &'a [Listen],
+}
+
+/// The track currently playing and how long it's been heard.
+struct Current {
+    listen: Listen,
+    /// Heard before the last pause.
+    heard: Duration,
+    /// When it last started or resumed playing; `None` while paused.
+    since: Option<Instant>,
+}
+
+impl Current {
+    fn heard(&self) -> Duration {
+        self.heard + self.since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    fn pause(&mut self) {
+        self.heard = self.heard();
+        self.since = None;
+    }
+
+    fn resume(&mut self) {
+        self.since.get_or_insert_with(Instant::now);
+    }
+}
+
+/// Scrobbles what `player` plays until the player goes away.
+pub async fn serve(player: Arc<AudioPlayer>, options: ListenBrainzOptions) {
+    let mut scrobbler = Scrobbler::new(options);
+    let mut events = player.subscribe();
+    let mut retry = tokio::time::interval(RETRY_INTERVAL);
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => scrobbler.handle(event).await,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            _ = retry.tick() => scrobbler.flush().await,
+        }
+    }
+
+    scrobbler.finish().await;
+}
+
+/// Turns player events into listens. A paused track keeps its start time and
+/// is scrobbled once, when it ends.
+struct Scrobbler {
+    client: reqwest::Client,
+    options: ListenBrainzOptions,
+    /// The station's latest title, which may have changed while paused.
+    title: Option<String>,
+    current: Option<Current>,
+    paused: bool,
+}
+
+impl Scrobbler {
+    fn new(options: ListenBrainzOptions) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            options,
+            title: None,
+            current: None,
+            paused: false,
+        }
+    }
+
+    async fn handle(&mut self, event: PlayerEvent) {
+        match event {
+            PlayerEvent::TrackChanged { title } => {
+                self.finish().await;
+                if !self.paused {
+                    self.start(&title).await;
+                }
+                self.title = Some(title);
+            }
+            PlayerEvent::Paused => {
+                self.paused = true;
+                if let Some(current) = &mut self.current {
+                    current.pause();
+                }
+            }
+            PlayerEvent::Resumed => {
+                self.paused = false;
+                match &mut self.current {
+                    Some(current) => current.resume(),
+                    // The track changed while paused, so this one starts now.
+                    None => {
+                        if let Some(title) = self.title.clone() {
+                            self.start(&title).await;
+                        }
+                    }
+                }
+            }
+            PlayerEvent::Playing { .. } | PlayerEvent::Stopped => {
+                self.paused = false;
+                self.title = None;
+                self.finish().await;
+            }
+            PlayerEvent::VolumeChanged { .. }
+            | PlayerEvent::DeadAir { .. }
+            | PlayerEvent::FellBack { .. }
+            | PlayerEvent::Unavailable { .. } => {}
+        }
+    }
+
+    /// Announces `title` as playing now and starts timing it.
+    async fn start(&mut self, title: &str) {
+        let Some(listen) = Listen::starting_now(title) else { return };
+        let playing_now = Listen {
+            listened_at: None,
+            ..listen.clone()
+        };
+        // Playing-now updates are only useful live, so they're never queued.
+        let _ = self.submit("playing_now", &[playing_now]).await;
+        self.current = Some(Current {
+            listen,
+            heard: Duration::ZERO,
+            since: Some(Instant::now()),
+        });
+    }
+
+    /// Queues the current track if it played long enough, then sends the queue.
+    async fn finish(&mut self) {
+        let Some(current) = self.current.take() else { return };
+        if current.heard() < self.options.min_listen {
+            return;
+        }
+        let mut queue = self.load_queue();
+        queue.push(current.listen);
+        let _ = self.save_queue(&queue);
+        self.flush().await;
+    }
+
+    /// Sends queued listens in batches, stopping at the first failure that's
+    /// worth retrying. Listens the server rejects outright are dropped.
+    async fn flush(&self) {
+        loop {
+            let queue = self.load_queue();
+            if queue.is_empty() {
+                return;
+            }
+            let batch: Vec<Listen> = queue.into_iter().take(BATCH_SIZE).collect();
+            let listen_type = if batch.len() == 1 { "single" } else { "import" };
+
+            match self.submit(listen_type, &batch).await {
+                Ok(()) | Err(Failure::Rejected) => {
+                    // Re-read, since another termadio may have queued listens meanwhile.
+                    let mut queue = self.load_queue();
+                    queue.retain(|listen| !batch.contains(listen));
+                    if self.save_queue(&queue).is_err() {
+                        return;
+                    }
+                }
+                Err(Failure::Retry) => return,
+            }
+        }
+    }
+
+    async fn submit(&self, listen_type: &'static str, payload: &[Listen]) -> Result<(), Failure> {
+        let response = self
+            .client
+            .post(format!("{}/1/submit-listens", self.options.url))
+            .header("Authorization", format!("Token {}", self.options.token))
+            .json(&Submission { listen_type, payload })
+            .timeout(Duration::from_secs(30))
+            .send()
+            .await
+            .map_err(|_| Failure::Retry)?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            StatusCode::UNAUTHORIZED | StatusCode::TOO_MANY_REQUESTS => Err(Failure::Retry),
+            status if status.is_client_error() => Err(Failure::Rejected),
+            _ => Err(Failure::Retry),
+        }
+    }
+
+    fn load_queue(&self) -> Vec<Listen> {
+        fs::read_to_string(&self.options.queue)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save_queue(&self, queue: &[Listen]) -> Result<()> {
+        if queue.is_empty() {
+            if self.options.queue.exists() {
+                fs::remove_file(&self.options.queue)?;
+            }
+            return Ok(());
+        }
+        let content = serde_json::to_string_pretty(queue)?;
+        fs::write(&self.options.queue, content)?;
+        Ok(())
+    }
+}
+
+enum Failure {
+    /// Offline, rate limited, a bad token or a server error: try again later.
+    Retry,
+    /// The server won't ever accept these listens.
+    Rejected,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::StatusCode as Status;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use serde_json::Value;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+    use tokio::net::TcpListener;
+
+    /// What the stand-in server was sent, and the statuses it answers with
+    /// (200 once they run out).
+    #[derive(Default)]
+    struct Server {
+        submissions: Vec<Value>,
+        statuses: VecDeque<Status>,
+    }
+
+    async fn submit_listens(State(server): State<Arc<Mutex<Server>>>, Json(body): Json<Value>) -> Status {
+        let mut server = server.lock().unwrap();
+        server.submissions.push(body);
+        server.statuses.pop_front().unwrap_or(Status::OK)
+    }
+
+    async fn stand_in(statuses: &[Status]) -> (String, Arc<Mutex<Server>>) {
+        let server = Arc::new(Mutex::new(Server { statuses: statuses.iter().copied().collect(), ..Server::default() }));
+        let router = Router::new().route("/1/submit-listens", post(submit_listens)).with_state(Arc::clone(&server));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        (url, server)
+    }
+
+    /// A URL nothing listens on.
+    async fn offline() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    fn scrobbler(test: &str, url: &str, min_listen: Duration) -> Scrobbler {
+        let queue = std::env::temp_dir().join(format!("termadio-listenbrainz-{}-{}.json", std::process::id(), test));
+        let _ = fs::remove_file(&queue);
+        Scrobbler::new(ListenBrainzOptions { url: url.to_string(), token: "secret".to_string(), min_listen, queue })
+    }
+
+    fn submissions(server: &Mutex<Server>) -> Vec<Value> {
+        server.lock().unwrap().submissions.clone()
+    }
+
+    fn track_names(submission: &Value) -> Vec<&str> {
+        submission["payload"].as_array().unwrap().iter().map(|listen| listen["track_metadata"]["track_name"].as_str().unwrap()).collect()
+    }
+
+    fn listens(count: usize) -> Vec<Listen> {
+        (0..count).map(|i| Listen::starting_now(&format!("Artist - Track {}", i)).unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn announces_tracks_as_playing_now() {
+        let (url, server) = stand_in(&[]).await;
+        let mut scrobbler = scrobbler("playing-now", &url, Duration::from_secs(3600));
+        scrobbler.handle(PlayerEvent::TrackChanged { title: "Nina Simone - Sinnerman".to_string() }).await;
+        // Station IDs and jingles aren't tracks.
+        scrobbler.handle(PlayerEvent::TrackChanged { title: "Jazz FM".to_string() }).await;
+
+        let submissions = submissions(&server);
+        assert_eq!(submissions.len(), 1);
+        assert_eq!(submissions[0]["listen_type"], "playing_now");
+        let listen = &submissions[0]["payload"][0];
+        assert!(listen.get("listened_at").is_none());
+        assert_eq!(listen["track_metadata"]["artist_name"], "Nina Simone");
+        assert_eq!(listen["track_metadata"]["track_name"], "Sinnerman");
+    }
+
+    #[tokio::test]
+    async fn skips_tracks_heard_too_briefly() {
+        let (url, server) = stand_in(&[]).await;
+        let mut scrobbler = scrobbler("too-brief", &url, Duration::from_secs(3600));
+        scrobbler.handle(PlayerEvent::TrackChanged { title: "Artist - One".to_string() }).await;
+        scrobbler.handle(PlayerEvent::Stopped).await;
+
+        assert_eq!(submissions(&server).len(), 1);
+        assert!(!scrobbler.options.queue.exists());
+    }
+
+    #[tokio::test]
+    async fn scrobbles_a_paused_track_once_with_its_start_time() {
+        let (url, server) = stand_in(&[]).await;
+        let mut scrobbler = scrobbler("paused", &url, Duration::ZERO);
+        scrobbler.handle(PlayerEvent::TrackChanged { title: "Artist - One".to_string() }).await;
+        let listened_at = scrobbler.current.as_ref().unwrap().listen.listened_at;
+        scrobbler.handle(PlayerEvent::Paused).await;
+        scrobbler.handle(PlayerEvent::Resumed).await;
+        scrobbler.handle(PlayerEvent::TrackChanged { title: "Artist - Two".to_string() }).await;
+
+        let submissions = submissions(&server);
+        let types: Vec<&str> = submissions.iter().map(|s| s["listen_type"].as_str().unwrap()).collect();
+        assert_eq!(types, ["playing_now", "single", "playing_now"]);
+        assert_eq!(track_names(&submissions[1]), ["One"]);
+        assert_eq!(submissions[1]["payload"][0]["listened_at"].as_i64(), listened_at);
+    }
+
+    #[tokio::test]
+    async fn a_track_changed_while_paused_starts_on_resume() {
+        let (url, server) = stand_in(&[]).await;
+        let mut scrobbler = scrobbler("changed-while-paused", &url, Duration::from_secs(3600));
+        scrobbler.handle(PlayerEvent::Paused).await;
+        scrobbler.handle(PlayerEvent::TrackChanged { title: "Artist - One".to_string() }).await;
+        assert!(submissions(&server).is_empty());
+
+        scrobbler.handle(PlayerEvent::Resumed).await;
+        let submissions = submissions(&server);
+        assert_eq!(submissions.len(), 1);
+        assert_eq!(track_names(&submissions[0]), ["One"]);
+    }
+
+    #[test]
+    fn pauses_dont_count_as_listening() {
+        let mut current = Current { listen: listens(1).remove(0), heard: Duration::from_secs(30), since: None };
+        assert_eq!(current.heard(), Duration::from_secs(30));
+        current.resume();
+        assert!(current.heard() >= Duration::from_secs(30));
+        current.pause();
+        let heard = current.heard();
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(current.heard(), heard);
+    }
+
+    #[tokio::test]
+    async fn queues_listens_while_offline() {
+        let mut scrobbler = scrobbler("offline", &offline().await, Duration::ZERO);
+        scrobbler.handle(PlayerEvent::TrackChanged { title: "Artist - One".to_string() }).await;
+        scrobbler.handle(PlayerEvent::Stopped).await;
+        let queued = scrobbler.load_queue();
+        assert_eq!(queued.len(), 1);
+        assert_eq!(queued[0].track_metadata.track_name, "One");
+
+        let (url, server) = stand_in(&[]).await;
+        scrobbler.options.url = url;
+        scrobbler.flush().await;
+        let submissions = submissions(&server);
+        assert_eq!(submissions.len(), 1);
+        assert_eq!(submissions[0]["listen_type"], "single");
+        assert!(!scrobbler.options.queue.exists());
+    }
+
+    #[tokio::test]
+    async fn flushes_the_queue_in_batches() {
+        let (url, server) = stand_in(&[]).await;
+        let scrobbler = scrobbler("batches", &url, Duration::ZERO);
+        scrobbler.save_queue(&listens(BATCH_SIZE + 1)).unwrap();
+        scrobbler.flush().await;
+
+        let submissions = submissions(&server);
+        assert_eq!(submissions.len(), 2);
+        assert_eq!(submissions[0]["listen_type"], "import");
+        assert_eq!(track_names(&submissions[0]).len(), BATCH_SIZE);
+        assert_eq!(submissions[1]["listen_type"], "single");
+        assert_eq!(track_names(&submissions[1]), [format!("Track {}", BATCH_SIZE)]);
+        assert!(!scrobbler.options.queue.exists());
+    }
+
+    #[tokio::test]
+    async fn drops_rejected_batches() {
+        let (url, server) = stand_in(&[Status::BAD_REQUEST]).await;
+        let scrobbler = scrobbler("rejected", &url, Duration::ZERO);
+        scrobbler.save_queue(&listens(BATCH_SIZE + 1)).unwrap();
+        scrobbler.flush().await;
+
+        assert_eq!(submissions(&server).len(), 2);
+        assert!(!scrobbler.options.queue.exists());
+    }
+
+    #[tokio::test]
+    async fn keeps_batches_to_retry() {
+        for status in [Status::SERVICE_UNAVAILABLE, Status::UNAUTHORIZED, Status::TOO_MANY_REQUESTS] {
+            let (url, server) = stand_in(&[status]).await;
+            let scrobbler = scrobbler("retry", &url, Duration::ZERO);
+            scrobbler.save_queue(&listens(BATCH_SIZE + 1)).unwrap();
+            scrobbler.flush().await;
+
+            assert_eq!(submissions(&server).len(), 1, "{}", status);
+            assert_eq!(scrobbler.load_queue().len(), BATCH_SIZE + 1, "{}", status);
+            fs::remove_file(&scrobbler.options.queue).unwrap();
+        }
+    }
+}
@@ -1,15 +1,28 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::net::IpAddr;
+use std::path::PathBuf;
 
 mod api;
 mod commands;
 pub mod config;
+mod daemon;
+mod http;
+mod icecast;
+mod listenbrainz;
 mod preferences;
 mod ui;
 mod player;
 mod favorites;
+mod history;
+mod search_history;
+mod stats;
+#[cfg(target_os = "linux")]
+mod mpris;
 
-use commands::{hello, info, search, country, radio};
+use commands::{hello, info, search, country, radio, ctl};
+use commands::history::OutputFormat;
+use stats::Period;
 
 #[derive(Parser)]
 #[command(name = "termadio")]
@@ -27,7 +40,14 @@ struct Cli {
 #[derive(Subcommand)]
 enum Commands {
     /// Launch interactive radio terminal (default)
-    Radio,
+    Radio {
+        /// Control a running `termadio daemon` instead of playing audio locally
+        #[arg(long)]
+        attach: bool,
+        /// Daemon control socket (defaults to $XDG_RUNTIME_DIR/termadio.sock)
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
     /// Search for radio stations or countries
     Search {
         /// Search query (station or country name)
@@ -55,6 +75,104 @@ enum Commands {
     },
     /// Show system information
     Info,
+    /// Show what you've been listening to
+    History {
+        /// Only show sessions from this period, e.g. 12h, 7d or 2w
+        #[arg(long)]
+        since: Option<String>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Show listening statistics
+    Stats {
+        /// Period to summarize
+        #[arg(long, value_enum, default_value_t = Period::Week)]
+        period: Period,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Run the player headless, controlled through a local socket
+    Daemon {
+        /// Control socket path (defaults to $XDG_RUNTIME_DIR/termadio.sock)
+        #[arg(long)]
+        socket: Option<PathBuf>,
+        /// Also serve the HTTP remote-control API
+        #[arg(long)]
+        http: bool,
+        /// Re-broadcast the playing stream at /stream (starts the HTTP server)
+        #[arg(long)]
+        relay: bool,
+        /// Address for the HTTP server (defaults to the config's http.bind)
+        #[arg(long)]
+        bind: Option<String>,
+    },
+    /// Run the player headless and serve a browser UI to control it
+    Web {
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+        /// Address to listen on (defaults to the config's http.bind address)
+        #[arg(long)]
+        host: Option<IpAddr>,
+        /// Control socket path (defaults to $XDG_RUNTIME_DIR/termadio.sock)
+        #[arg(long)]
+        socket: Option<PathBuf>,
+        /// Also re-broadcast the playing stream at /stream
+        #[arg(long)]
+        relay: bool,
+    },
+    /// Run the daemon and push what it plays to an Icecast mount
+    Broadcast {
+        /// Icecast mount URL, e.g. http://host:8000/radio
+        #[arg(long)]
+        server: String,
+        /// Source password for the mount
+        #[arg(long)]
+        password: String,
+        /// Source user name
+        #[arg(long, default_value = "source")]
+        user: String,
+        /// Station ID to start broadcasting right away
+        #[arg(long)]
+        station: Option<String>,
+        /// Control socket path (defaults to $XDG_RUNTIME_DIR/termadio.sock)
+        #[arg(long)]
+        socket: Option<PathBuf>,
+    },
+    /// Control a running daemon
+    Ctl {
+        /// Control socket path (defaults to $XDG_RUNTIME_DIR/termadio.sock)
+        #[arg(long, global = true)]
+        socket: Option<PathBuf>,
+        #[command(subcommand)]
+        action: CtlAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum CtlAction {
+    /// Play a station by ID
+    Play {
+        /// Station ID
+        id: String,
+    },
+    /// Pause playback
+    Pause,
+    /// Resume playback
+    Resume,
+    /// Stop playback
+    Stop,
+    /// Set the volume
+    Volume {
+        /// Volume in percent (0-100)
+        level: u8,
+    },
+    /// Show what the daemon is playing
+    Status,
+    /// Print player events as JSON lines until interrupted
+    Events,
 }
 
 #[derive(Subcommand)]
@@ -88,14 +206,26 @@ enum FavoritesAction {
         /// Station ID
         id: String,
     },
+    /// Add a stream to try when a favorite station fails to play
+    AddFallback {
+        /// Station ID of the favorite
+        id: String,
+        /// Another station ID, or a direct stream URL
+        stream: String,
+    },
+    /// Remove all fallbacks from a favorite station
+    ClearFallbacks {
+        /// Station ID of the favorite
+        id: String,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
     match &cli.command {
-        Some(Commands::Radio) => {
-            radio::run().await?;
+        Some(Commands::Radio { attach, socket }) => {
+            radio::run(*attach, socket.clone()).await?;
         }
         Some(Commands::Search { query }) => {
             search::run(query, cli.verbose).await?;
@@ -109,13 +239,19 @@ async fn main() -> Result<()> {
         Some(Commands::Info) => {
             info::run(cli.verbose)?;
         }
+        Some(Commands::History { since, format }) => {
+            commands::history::run(since.as_deref(), *format)?;
+        }
+        Some(Commands::Stats { period, format }) => {
+            commands::stats::run(*period, *format)?;
+        }
         Some(Commands::Preferences { country_id }) => {
             println!("Country ID: {}", country_id);
             let storage =
                 preferences::storage::PreferencesStorage::new("preferences.json".to_string());
             let user_prefs = preferences::storage::UserPreferences {
-                countryId: Some(country_id.clone()),
-                favoriteChannl: None,
+                country_id: Some(country_id.clone()),
+                favorite_channel: None,
             };
             storage.save_preferences(user_prefs)?;
         }
@@ -136,10 +272,45 @@ async fn main() -> Result<()> {
                 FavoritesAction::RemoveStation { id } => {
                     commands::favorites::remove_station(id)?;
                 }
+                FavoritesAction::AddFallback { id, stream } => {
+                    commands::favorites::add_fallback(id, stream)?;
+                }
+                FavoritesAction::ClearFallbacks { id } => {
+                    commands::favorites::clear_fallbacks(id)?;
+                }
+            }
+        }
+        Some(Commands::Daemon { socket, http, relay, bind }) => {
+            commands::daemon::run(socket.clone(), *http, *relay, bind.clone(), cli.verbose).await?;
+        }
+        Some(Commands::Web { port, host, socket, relay }) => {
+            commands::web::run(*port, *host, socket.clone(), *relay, cli.verbose).await?;
+        }
+        Some(Commands::Broadcast { server, password, user, station, socket }) => {
+            commands::broadcast::run(
+                server,
+                user.clone(),
+                password.clone(),
+                station.clone(),
+                socket.clone(),
+                cli.verbose,
+            )
+            .await?;
+        }
+        Some(Commands::Ctl { socket, action }) => {
+            let socket = socket.clone();
+            match action {
+                CtlAction::Play { id } => ctl::play(socket, id)?,
+                CtlAction::Pause => ctl::pause(socket)?,
+                CtlAction::Resume => ctl::resume(socket)?,
+                CtlAction::Stop => ctl::stop(socket)?,
+                CtlAction::Volume { level } => ctl::volume(socket, *level)?,
+                CtlAction::Status => ctl::status(socket)?,
+                CtlAction::Events => ctl::events(socket)?,
             }
         }
         None => {
-            radio::run().await?;
+            radio::run(false, None).await?;
         }
     }
 
@@ -0,0 +1,486 @@
+//! MPRIS D-Bus service so desktop media keys, shell widgets and `playerctl`
+//! can control the player. See https://specifications.freedesktop.org/mpris-spec/latest/
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use zbus::fdo;
+use zbus::zvariant::{ObjectPath, OwnedValue, Str, Value};
+use zbus::{interface, Connection};
+
+use crate::player::icy::split_artist_title;
+use crate::player::{AudioPlayer, PlaybackState, PlayerEvent, PlayerStatus, StationInfo};
+
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.termadio";
+
+struct Root;
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> &str {
+        "termadio"
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// What the MPRIS interface drives: the `AudioPlayer`, or a stand-in in tests.
+trait MediaPlayer: Send + Sync {
+    fn status(&self) -> PlayerStatus;
+    fn pause(&self);
+    fn resume(&self);
+    fn stop(&self);
+    fn skip_favorite(&self, forward: bool) -> Result<Option<StationInfo>>;
+    fn volume(&self) -> u8;
+    fn set_volume(&self, volume: u8);
+    fn subscribe(&self) -> broadcast::Receiver<PlayerEvent>;
+}
+
+impl MediaPlayer for AudioPlayer {
+    fn status(&self) -> PlayerStatus {
+        AudioPlayer::status(self)
+    }
+
+    fn pause(&self) {
+        AudioPlayer::pause(self)
+    }
+
+    fn resume(&self) {
+        AudioPlayer::resume(self)
+    }
+
+    fn stop(&self) {
+        AudioPlayer::stop(self)
+    }
+
+    fn skip_favorite(&self, forward: bool) -> Result<Option<StationInfo>> {
+        AudioPlayer::skip_favorite(self, forward)
+    }
+
+    fn volume(&self) -> u8 {
+        AudioPlayer::volume(self)
+    }
+
+    fn set_volume(&self, volume: u8) {
+        AudioPlayer::set_volume(self, volume)
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+        AudioPlayer::subscribe(self)
+    }
+}
+
+struct Player {
+    player: Arc<dyn MediaPlayer>,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn next(&self) -> fdo::Result<()> {
+        self.player.skip_favorite(true).map_err(to_fdo)?;
+        Ok(())
+    }
+
+    fn previous(&self) -> fdo::Result<()> {
+        self.player.skip_favorite(false).map_err(to_fdo)?;
+        Ok(())
+    }
+
+    fn pause(&self) {
+        self.player.pause();
+    }
+
+    fn play_pause(&self) -> fdo::Result<()> {
+        match self.player.status().state {
+            PlaybackState::Playing => self.player.pause(),
+            PlaybackState::Paused => self.player.resume(),
+            PlaybackState::Stopped => self.play()?,
+        }
+        Ok(())
+    }
+
+    fn stop(&self) {
+        self.player.stop();
+    }
+
+    /// Resumes when paused; when stopped, starts the first favorite station.
+    fn play(&self) -> fdo::Result<()> {
+        match self.player.status().state {
+            PlaybackState::Playing => {}
+            PlaybackState::Paused => self.player.resume(),
+            PlaybackState::Stopped => {
+                self.player.skip_favorite(true).map_err(to_fdo)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Live streams can't seek.
+    fn seek(&self, _offset: i64) {}
+
+    fn set_position(&self, _track_id: ObjectPath<'_>, _position: i64) {}
+
+    fn open_uri(&self, _uri: &str) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported("Opening URIs is not supported".to_string()))
+    }
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        match self.player.status().state {
+            PlaybackState::Playing => "Playing",
+            PlaybackState::Paused => "Paused",
+            PlaybackState::Stopped => "Stopped",
+        }
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn set_rate(&self, _rate: f64) {}
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let status = self.player.status();
+        let mut metadata = HashMap::new();
+        let Some(station) = status.station else {
+            return metadata;
+        };
+
+        // Track IDs must be valid object paths, and station IDs are alphanumeric.
+        let track_id = format!(
+            "/org/mpris/MediaPlayer2/termadio/{}",
+            station.id.replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+        );
+        if let Ok(path) = ObjectPath::try_from(track_id) {
+            metadata.insert("mpris:trackid".to_string(), OwnedValue::from(path));
+        }
+
+        let (artist, title) = match status.track.as_deref() {
+            Some(track) => split_artist_title(track),
+            None => (None, station.title.as_str()),
+        };
+        metadata.insert("xesam:title".to_string(), OwnedValue::from(Str::from(title.to_string())));
+        metadata.insert("xesam:album".to_string(), OwnedValue::from(Str::from(station.title.clone())));
+        if let Some(artist) = artist {
+            if let Ok(artists) = OwnedValue::try_from(Value::from(vec![artist.to_string()])) {
+                metadata.insert("xesam:artist".to_string(), artists);
+            }
+        }
+        metadata
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.player.volume() as f64 / 100.0
+    }
+
+    #[zbus(property)]
+    fn set_volume(&self, volume: f64) {
+        self.player.set_volume((volume.clamp(0.0, 1.0) * 100.0).round() as u8);
+    }
+
+    #[zbus(property)]
+    fn position(&self) -> i64 {
+        0
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+fn to_fdo(e: anyhow::Error) -> fdo::Error {
+    fdo::Error::Failed(e.to_string())
+}
+
+/// Publishes `player` on the session bus and keeps its properties in sync
+/// until the player goes away. Fails if there is no session bus.
+pub async fn serve(player: Arc<AudioPlayer>) -> Result<()> {
+    serve_on(Connection::session().await?, player).await
+}
+
+async fn serve_on(connection: Connection, player: Arc<dyn MediaPlayer>) -> Result<()> {
+    let mut events = player.subscribe();
+
+    connection.object_server().at(OBJECT_PATH, Root).await?;
+    connection
+        .object_server()
+        .at(OBJECT_PATH, Player { player })
+        .await?;
+
+    // A second termadio (say, a TUI next to a daemon) registers as its own instance.
+    if connection.request_name(BUS_NAME).await.is_err() {
+        let instance = format!("{}.instance{}", BUS_NAME, std::process::id());
+        connection.request_name(instance).await?;
+    }
+
+    let iface_ref = connection
+        .object_server()
+        .interface::<_, Player>(OBJECT_PATH)
+        .await?;
+
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        let iface = iface_ref.get().await;
+        let ctxt = iface_ref.signal_context();
+        match event {
+            PlayerEvent::Playing { .. } | PlayerEvent::Stopped => {
+                iface.playback_status_changed(ctxt).await?;
+                iface.metadata_changed(ctxt).await?;
+            }
+            PlayerEvent::Paused | PlayerEvent::Resumed => {
+                iface.playback_status_changed(ctxt).await?;
+            }
+            PlayerEvent::TrackChanged { .. } => {
+                iface.metadata_changed(ctxt).await?;
+            }
+            PlayerEvent::VolumeChanged { .. } => {
+                iface.volume_changed(ctxt).await?;
+            }
+            PlayerEvent::DeadAir { .. } | PlayerEvent::FellBack { .. } | PlayerEvent::Unavailable { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use zbus::{connection, Proxy};
+
+    /// A player that reports whatever status the test sets.
+    struct StandIn {
+        status: Mutex<PlayerStatus>,
+        events: broadcast::Sender<PlayerEvent>,
+    }
+
+    impl StandIn {
+        fn new() -> Self {
+            let status = PlayerStatus {
+                state: PlaybackState::Stopped,
+                station: None,
+                track: None,
+                volume: 50,
+                buffered: 0,
+                received: 0,
+            };
+            Self { status: Mutex::new(status), events: broadcast::channel(8).0 }
+        }
+
+        /// Changes the status, then announces it with `event`.
+        fn update(&self, event: PlayerEvent, change: impl FnOnce(&mut PlayerStatus)) {
+            change(&mut self.status.lock().unwrap());
+            self.events.send(event).unwrap();
+        }
+    }
+
+    impl MediaPlayer for StandIn {
+        fn status(&self) -> PlayerStatus {
+            self.status.lock().unwrap().clone()
+        }
+
+        fn pause(&self) {}
+
+        fn resume(&self) {}
+
+        fn stop(&self) {}
+
+        fn skip_favorite(&self, _forward: bool) -> Result<Option<StationInfo>> {
+            Ok(None)
+        }
+
+        fn volume(&self) -> u8 {
+            self.status.lock().unwrap().volume
+        }
+
+        fn set_volume(&self, volume: u8) {
+            self.status.lock().unwrap().volume = volume;
+        }
+
+        fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+            self.events.subscribe()
+        }
+    }
+
+    /// A private session bus, shut down on drop.
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        /// `None` when `dbus-daemon` isn't installed or won't start.
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address=1"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            let read = daemon.stdout.take().map(|stdout| BufReader::new(stdout).read_line(&mut address));
+            let bus = Self { daemon, address: address.trim().to_string() };
+            (matches!(read, Some(Ok(_))) && !bus.address.is_empty()).then_some(bus)
+        }
+
+        async fn connect(&self) -> Connection {
+            connection::Builder::address(self.address.as_str()).unwrap().build().await.unwrap()
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    /// Waits for the cached `property` to become `expected`, which it only
+    /// does through a `PropertiesChanged` signal.
+    async fn changed_to(proxy: &Proxy<'_>, property: &str, expected: OwnedValue) {
+        let waiting = async {
+            while proxy.cached_property_raw(property).as_deref() != Some(&*expected) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), waiting)
+            .await
+            .unwrap_or_else(|_| panic!("{} didn't change to {:?}", property, expected));
+    }
+
+    fn string(s: &str) -> OwnedValue {
+        OwnedValue::from(Str::from(s.to_string()))
+    }
+
+    #[tokio::test]
+    async fn publishes_playback_status_and_metadata() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("dbus-daemon isn't available, skipping");
+            return;
+        };
+        let player = Arc::new(StandIn::new());
+        tokio::spawn(serve_on(bus.connect().await, Arc::clone(&player) as Arc<dyn MediaPlayer>));
+
+        let client = bus.connect().await;
+        let dbus = fdo::DBusProxy::new(&client).await.unwrap();
+        let name = zbus::names::BusName::try_from(BUS_NAME).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !dbus.name_has_owner(name.clone()).await.unwrap() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the player never appeared on the bus");
+
+        let proxy = Proxy::new(&client, BUS_NAME, OBJECT_PATH, "org.mpris.MediaPlayer2.Player").await.unwrap();
+        assert_eq!(proxy.get_property::<String>("PlaybackStatus").await.unwrap(), "Stopped");
+        assert!(proxy.get_property::<HashMap<String, OwnedValue>>("Metadata").await.unwrap().is_empty());
+        assert_eq!(proxy.get_property::<f64>("Volume").await.unwrap(), 0.5);
+
+        let station = StationInfo { id: "ab-12".to_string(), title: "Jazz FM".to_string(), country: Some("France".to_string()) };
+        player.update(PlayerEvent::Playing { station: station.clone() }, |status| {
+            status.state = PlaybackState::Playing;
+            status.station = Some(station);
+            status.track = Some("Nina Simone - Sinnerman".to_string());
+        });
+        changed_to(&proxy, "PlaybackStatus", string("Playing")).await;
+        let metadata: HashMap<String, OwnedValue> = proxy.get_property("Metadata").await.unwrap();
+        assert_eq!(metadata["xesam:title"], string("Sinnerman"));
+        assert_eq!(metadata["xesam:album"], string("Jazz FM"));
+        let artists = OwnedValue::try_from(Value::from(vec!["Nina Simone".to_string()])).unwrap();
+        assert_eq!(metadata["xesam:artist"], artists);
+        let track_id = ObjectPath::try_from("/org/mpris/MediaPlayer2/termadio/ab_12").unwrap();
+        assert_eq!(metadata["mpris:trackid"], OwnedValue::from(track_id));
+
+        // A title that isn't `Artist - Title` is shown whole, with no artist.
+        player.update(PlayerEvent::TrackChanged { title: "Jazz FM jingle".to_string() }, |status| {
+            status.track = Some("Jazz FM jingle".to_string());
+        });
+        let mut expected = HashMap::new();
+        expected.insert("mpris:trackid".to_string(), OwnedValue::from(ObjectPath::try_from("/org/mpris/MediaPlayer2/termadio/ab_12").unwrap()));
+        expected.insert("xesam:title".to_string(), string("Jazz FM jingle"));
+        expected.insert("xesam:album".to_string(), string("Jazz FM"));
+        changed_to(&proxy, "Metadata", OwnedValue::try_from(Value::from(expected)).unwrap()).await;
+
+        player.update(PlayerEvent::Paused, |status| status.state = PlaybackState::Paused);
+        changed_to(&proxy, "PlaybackStatus", string("Paused")).await;
+    }
+}
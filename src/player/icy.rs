@@ -0,0 +1,241 @@
+//! Shoutcast/Icecast in-band metadata.
+//!
+//! When a client sends `Icy-MetaData: 1`, the server interleaves a metadata
+//! block every `icy-metaint` bytes of audio: one length byte (in units of 16
+//! bytes) followed by that many bytes of `StreamTitle='...';` style fields.
+
+enum State {
+    Audio { remaining: usize },
+    Length,
+    Metadata { remaining: usize },
+}
+
+/// Splits an ICY stream into audio bytes and track titles, across chunk boundaries.
+pub struct IcyReader {
+    metaint: usize,
+    state: State,
+    metadata: Vec<u8>,
+}
+
+impl IcyReader {
+    pub fn new(metaint: usize) -> Self {
+        Self {
+            metaint,
+            state: State::Audio { remaining: metaint },
+            metadata: Vec::new(),
+        }
+    }
+
+    /// Appends the audio part of `chunk` to `audio`, returning the title from
+    /// any metadata block completed by this chunk.
+    pub fn feed(&mut self, mut chunk: &[u8], audio: &mut Vec<u8>) -> Option<String> {
+        let mut title = None;
+
+        while !chunk.is_empty() {
+            match self.state {
+                State::Audio { remaining } => {
+                    let take = remaining.min(chunk.len());
+                    audio.extend_from_slice(&chunk[..take]);
+                    chunk = &chunk[take..];
+                    self.state = if take == remaining {
+                        State::Length
+                    } else {
+                        State::Audio { remaining: remaining - take }
+                    };
+                }
+                State::Length => {
+                    let length = chunk[0] as usize * 16;
+                    chunk = &chunk[1..];
+                    self.metadata.clear();
+                    self.state = if length == 0 {
+                        State::Audio { remaining: self.metaint }
+                    } else {
+                        State::Metadata { remaining: length }
+                    };
+                }
+                State::Metadata { remaining } => {
+                    let take = remaining.min(chunk.len());
+                    self.metadata.extend_from_slice(&chunk[..take]);
+                    chunk = &chunk[take..];
+                    if take == remaining {
+                        let block = String::from_utf8_lossy(&self.metadata);
+                        if let Some(parsed) = parse_stream_title(&block) {
+                            title = Some(parsed);
+                        }
+                        self.state = State::Audio { remaining: self.metaint };
+                    } else {
+                        self.state = State::Metadata { remaining: remaining - take };
+                    }
+                }
+            }
+        }
+
+        title
+    }
+}
+
+/// Re-inserts metadata blocks into a stripped stream, for relaying to ICY clients.
+pub struct IcyWriter {
+    metaint: usize,
+    until_metadata: usize,
+    sent_title: Option<String>,
+}
+
+impl IcyWriter {
+    pub fn new(metaint: usize) -> Self {
+        Self {
+            metaint,
+            until_metadata: metaint,
+            sent_title: None,
+        }
+    }
+
+    /// Appends `audio` to `out`, with a metadata block every `metaint` bytes.
+    /// The title is only sent when it changed; other blocks are empty.
+    pub fn write(&mut self, mut audio: &[u8], title: Option<&str>, out: &mut Vec<u8>) {
+        while !audio.is_empty() {
+            let take = self.until_metadata.min(audio.len());
+            out.extend_from_slice(&audio[..take]);
+            audio = &audio[take..];
+            self.until_metadata -= take;
+
+            if self.until_metadata == 0 {
+                self.write_metadata(title, out);
+                self.until_metadata = self.metaint;
+            }
+        }
+    }
+
+    fn write_metadata(&mut self, title: Option<&str>, out: &mut Vec<u8>) {
+        let title = title.unwrap_or_default();
+        if self.sent_title.as_deref() == Some(title) {
+            out.push(0);
+            return;
+        }
+
+        // Single quotes would end the field early; the length byte caps blocks at 4080 bytes.
+        let mut block = format!("StreamTitle='{}';", title.replace('\'', "’")).into_bytes();
+        block.truncate(255 * 16);
+        let blocks = block.len().div_ceil(16);
+        block.resize(blocks * 16, 0);
+        out.push(blocks as u8);
+        out.extend_from_slice(&block);
+        self.sent_title = Some(title.to_string());
+    }
+}
+
+/// Extracts `StreamTitle` from a metadata block like `StreamTitle='A - B';StreamUrl='';`.
+pub fn parse_stream_title(block: &str) -> Option<String> {
+    let start = block.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &block[start..];
+    // Titles may contain quotes themselves, so look for the field terminator.
+    let end = rest.find("';").or_else(|| rest.rfind('\''))?;
+    let title = rest[..end].trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_string())
+    }
+}
+
+/// Splits the common `Artist - Title` convention; the artist is `None` when absent.
+pub fn split_artist_title(track: &str) -> (Option<&str>, &str) {
+    match track.split_once(" - ") {
+        Some((artist, title)) if !artist.trim().is_empty() && !title.trim().is_empty() => {
+            (Some(artist.trim()), title.trim())
+        }
+        _ => (None, track.trim()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `audio` with a metadata block of `metadata` (padded to 16 bytes) after every `metaint` bytes.
+    fn frame(audio: &[u8], metaint: usize, metadata: &[&str]) -> Vec<u8> {
+        let mut stream = Vec::new();
+        for (chunk, block) in audio.chunks(metaint).zip(metadata) {
+            stream.extend_from_slice(chunk);
+            let mut block = block.as_bytes().to_vec();
+            block.resize(block.len().div_ceil(16) * 16, 0);
+            stream.push((block.len() / 16) as u8);
+            stream.extend_from_slice(&block);
+        }
+        stream
+    }
+
+    #[test]
+    fn reader_strips_metadata_blocks() {
+        let audio: Vec<u8> = (0..32).collect();
+        let stream = frame(&audio, 8, &["", "StreamTitle='A - B';", "", "StreamTitle='C';"]);
+
+        let mut reader = IcyReader::new(8);
+        let mut out = Vec::new();
+        let mut titles = Vec::new();
+        // One byte at a time, so blocks and the length byte straddle chunks.
+        for byte in &stream {
+            titles.extend(reader.feed(std::slice::from_ref(byte), &mut out));
+        }
+        assert_eq!(out, audio);
+        assert_eq!(titles, ["A - B", "C"]);
+    }
+
+    #[test]
+    fn reader_reports_the_last_title_in_a_chunk() {
+        let audio = [7u8; 8];
+        let stream = frame(&audio, 4, &["StreamTitle='First';", "StreamTitle='Second';"]);
+        let mut out = Vec::new();
+        assert_eq!(IcyReader::new(4).feed(&stream, &mut out).as_deref(), Some("Second"));
+        assert_eq!(out, audio);
+    }
+
+    #[test]
+    fn writer_output_reads_back() {
+        let audio: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let mut writer = IcyWriter::new(64);
+        let mut stream = Vec::new();
+        // Uneven writes, with the title changing partway through and a quote to escape.
+        writer.write(&audio[..100], Some("A - B"), &mut stream);
+        writer.write(&audio[100..700], Some("A - B"), &mut stream);
+        writer.write(&audio[700..], Some("Rock 'n' Roll"), &mut stream);
+
+        let mut reader = IcyReader::new(64);
+        let mut out = Vec::new();
+        let mut titles = Vec::new();
+        for chunk in stream.chunks(37) {
+            titles.extend(reader.feed(chunk, &mut out));
+        }
+        assert_eq!(out, audio);
+        assert_eq!(titles, ["A - B", "Rock ’n’ Roll"]);
+    }
+
+    #[test]
+    fn writer_sends_unchanged_titles_as_empty_blocks() {
+        let mut writer = IcyWriter::new(4);
+        let mut stream = Vec::new();
+        writer.write(&[1, 2, 3, 4, 5, 6, 7, 8], Some("T"), &mut stream);
+        let block = b"StreamTitle='T';";
+        let mut expected = vec![1, 2, 3, 4, 1];
+        expected.extend_from_slice(block);
+        expected.extend_from_slice(&[5, 6, 7, 8, 0]);
+        assert_eq!(stream, expected);
+    }
+
+    #[test]
+    fn parses_stream_title() {
+        assert_eq!(parse_stream_title("StreamTitle='A - B';StreamUrl='';").as_deref(), Some("A - B"));
+        assert_eq!(parse_stream_title("StreamTitle='Rock 'n' Roll';").as_deref(), Some("Rock 'n' Roll"));
+        assert_eq!(parse_stream_title("StreamTitle='Unterminated").as_deref(), None);
+        assert_eq!(parse_stream_title("StreamTitle='  ';"), None);
+        assert_eq!(parse_stream_title("StreamUrl='x';"), None);
+    }
+
+    #[test]
+    fn splits_artist_and_title() {
+        assert_eq!(split_artist_title("Miles Davis - So What"), (Some("Miles Davis"), "So What"));
+        assert_eq!(split_artist_title("A - B - C"), (Some("A"), "B - C"));
+        assert_eq!(split_artist_title(" Just a title "), (None, "Just a title"));
+        assert_eq!(split_artist_title(" - Untitled"), (None, "- Untitled"));
+    }
+}
@@ -1,99 +1,322 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
+use bytes::Bytes;
 use rodio::{Decoder, OutputStream, Sink};
+use serde::{Deserialize, Serialize};
 use std::io::Cursor;
+use std::pin::Pin;
+use std::sync::mpsc as std_mpsc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use tokio::sync::mpsc;
-use futures_util::StreamExt;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use futures_util::{Stream, StreamExt};
+
+/// How long a stream may take to connect before the next one is tried.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long to wait for the initial buffer; a stream that sends less in that
+/// time is tried with what arrived, or skipped if nothing did.
+const BUFFER_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub mod icy;
+pub mod tap;
+pub mod watchdog;
+
+use icy::IcyReader;
+use tap::{SampleTap, TappedSource};
+use crate::api;
+use crate::favorites::Favorites;
+
+/// The station a stream belongs to, as shown to users and remote clients.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StationInfo {
+    pub id: String,
+    pub title: String,
+    pub country: Option<String>,
+}
+
+impl StationInfo {
+    /// Fills in a missing title from favorites, so `termadio ctl play <id>`
+    /// and remote clients can start a station by ID alone.
+    pub fn resolve(id: String, title: Option<String>, country: Option<String>) -> Self {
+        if let Some(title) = title {
+            return Self { id, title, country };
+        }
+        let favorite = Favorites::load()
+            .unwrap_or_default()
+            .stations
+            .into_iter()
+            .find(|s| s.id == id);
+        match favorite {
+            Some(favorite) => Self {
+                id,
+                title: favorite.title,
+                country: country.or(favorite.country),
+            },
+            None => Self {
+                title: id.clone(),
+                id,
+                country,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaybackState {
+    Stopped,
+    Playing,
+    Paused,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerStatus {
+    pub state: PlaybackState,
+    pub station: Option<StationInfo>,
+    /// Current track from the stream's ICY metadata, if it sends any.
+    #[serde(default)]
+    pub track: Option<String>,
+    /// Volume in percent, 0-100.
+    pub volume: u8,
+    /// Decoded chunks (each from up to 128KB of stream data) queued for
+    /// output, including the one playing; 0 while playing means starving.
+    #[serde(default)]
+    pub buffered: usize,
+    /// Bytes received from upstream so far, across stations.
+    #[serde(default)]
+    pub received: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PlayerEvent {
+    Playing { station: StationInfo },
+    Paused,
+    Resumed,
+    Stopped,
+    VolumeChanged { volume: u8 },
+    TrackChanged { title: String },
+    /// The station has been silent, or sent nothing, for too long.
+    /// `skipping` is set when the player is about to move to the next favorite.
+    DeadAir { station: StationInfo, reason: DeadAirReason, skipping: bool },
+    /// The station's own stream didn't play, so `stream`, one of its
+    /// fallbacks, is playing instead.
+    FellBack { station: StationInfo, stream: String },
+    /// Neither the station's stream nor any of its fallbacks could be played.
+    Unavailable { station: StationInfo, reason: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeadAirReason {
+    /// Audio keeps coming, but it's quiet.
+    Silence,
+    /// The connection is up, but no data arrives.
+    Stalled,
+}
+
+impl DeadAirReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            DeadAirReason::Silence => "silent",
+            DeadAirReason::Stalled => "stalled",
+        }
+    }
+}
+
+/// The raw (ICY-stripped, still encoded) stream, for relaying it elsewhere.
+#[derive(Debug, Clone)]
+pub enum StreamChunk {
+    /// A new upstream connection; everything after it uses this content type.
+    Started { content_type: String },
+    Audio(Bytes),
+}
 
 pub struct AudioPlayer {
-    _stream: OutputStream,
+    _output: OutputThread,
     sink: Arc<Mutex<Sink>>,
     current_handle: Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>,
+    tap: Arc<SampleTap>,
+    station: Arc<Mutex<Option<StationInfo>>>,
+    /// Counts plays, so a play's task can tell whether a later one took over.
+    plays: Arc<AtomicU64>,
+    track: Arc<Mutex<Option<String>>>,
+    /// Tracked separately because the sink also reports paused while buffering.
+    paused: Arc<AtomicBool>,
+    content_type: Arc<Mutex<Option<String>>>,
+    /// Bytes received from upstream, across stations.
+    received: Arc<AtomicU64>,
+    events: broadcast::Sender<PlayerEvent>,
+    chunks: broadcast::Sender<StreamChunk>,
+}
+
+/// Everything a streaming task publishes besides decoded audio.
+#[derive(Clone)]
+struct StreamOutlets {
+    track: Arc<Mutex<Option<String>>>,
+    content_type: Arc<Mutex<Option<String>>>,
+    received: Arc<AtomicU64>,
+    events: broadcast::Sender<PlayerEvent>,
+    chunks: broadcast::Sender<StreamChunk>,
+}
+
+/// Owns the rodio `OutputStream`, which isn't `Send`, on a thread of its own so
+/// the player itself can be shared between tasks. Dropping it closes the device.
+struct OutputThread {
+    _shutdown: std_mpsc::Sender<()>,
+}
+
+impl OutputThread {
+    fn spawn() -> Result<(Self, Sink)> {
+        let (shutdown_tx, shutdown_rx) = std_mpsc::channel::<()>();
+        let (sink_tx, sink_rx) = std_mpsc::channel();
+
+        std::thread::spawn(move || {
+            let opened = OutputStream::try_default()
+                .map_err(anyhow::Error::from)
+                .and_then(|(stream, handle)| Ok((stream, Sink::try_new(&handle)?)));
+            match opened {
+                Ok((_stream, sink)) => {
+                    let _ = sink_tx.send(Ok(sink));
+                    // Blocks until the player is dropped.
+                    let _ = shutdown_rx.recv();
+                }
+                Err(e) => {
+                    let _ = sink_tx.send(Err(e));
+                }
+            }
+        });
+
+        let sink = sink_rx.recv()??;
+        Ok((Self { _shutdown: shutdown_tx }, sink))
+    }
 }
 
 impl AudioPlayer {
     pub fn new() -> Result<Self> {
-        let (_stream, stream_handle) = OutputStream::try_default()?;
-        let sink = Sink::try_new(&stream_handle)?;
-        Ok(Self { 
-            _stream, 
+        let (output, sink) = OutputThread::spawn()?;
+        let (events, _) = broadcast::channel(64);
+        // Roughly 30s of 128kbps audio in 4-16KB network chunks.
+        let (chunks, _) = broadcast::channel(256);
+        Ok(Self {
+            _output: output,
             sink: Arc::new(Mutex::new(sink)),
             current_handle: Arc::new(Mutex::new(None)),
+            tap: Arc::new(SampleTap::new()),
+            station: Arc::new(Mutex::new(None)),
+            plays: Arc::new(AtomicU64::new(0)),
+            track: Arc::new(Mutex::new(None)),
+            paused: Arc::new(AtomicBool::new(false)),
+            content_type: Arc::new(Mutex::new(None)),
+            received: Arc::new(AtomicU64::new(0)),
+            events,
+            chunks,
         })
     }
 
-    pub fn play_url(&self, url: String) -> Result<()> {
+    /// Plays a Radio Garden station by ID, falling back to the streams its
+    /// favorite entry lists when the station's own stream won't play.
+    pub fn play_station(&self, station: StationInfo) -> Result<()> {
+        let mut streams = vec![station.id.clone()];
+        streams.extend(Favorites::load().unwrap_or_default().fallbacks(&station.id));
+        self.play_streams(streams, station)
+    }
+
+    /// Plays the first of `streams` (station IDs or URLs) that connects, sends
+    /// audio and decodes, trying the rest in order.
+    fn play_streams(&self, streams: Vec<String>, station: StationInfo) -> Result<()> {
         self.stop();
-        
+
         let sink = Arc::clone(&self.sink);
         let handle_ref = Arc::clone(&self.current_handle);
-        
+        let tap = Arc::clone(&self.tap);
+        let paused = Arc::clone(&self.paused);
+        paused.store(false, Ordering::Relaxed);
+        let outlets = StreamOutlets {
+            track: Arc::clone(&self.track),
+            content_type: Arc::clone(&self.content_type),
+            received: Arc::clone(&self.received),
+            events: self.events.clone(),
+            chunks: self.chunks.clone(),
+        };
+        let station_ref = Arc::clone(&self.station);
+        let plays = Arc::clone(&self.plays);
+        let play = self.plays.fetch_add(1, Ordering::SeqCst) + 1;
+        if let Ok(mut current) = self.station.lock() {
+            *current = Some(station.clone());
+        }
+
         let handle = tokio::spawn(async move {
             let client = reqwest::Client::builder()
                 .user_agent("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36")
+                .connect_timeout(CONNECT_TIMEOUT)
                 .build()
                 .unwrap();
-                
-            if let Ok(response) = client.get(&url).send().await {
-                let mut stream = response.bytes_stream();
-                let mut buffer = Vec::new();
-                
-                // Collect larger initial buffer for decoder
-                while let Some(chunk_result) = stream.next().await {
-                    if let Ok(chunk) = chunk_result {
-                        buffer.extend_from_slice(&chunk);
-                        if buffer.len() >= 131072 { // 128KB initial buffer
-                            break;
-                        }
-                    }
-                }
-                
-                if !buffer.is_empty() {
-                    let cursor = Cursor::new(buffer.clone());
-                    if let Ok(source) = Decoder::new(cursor) {
-                        if let Ok(sink_guard) = sink.lock() {
-                            sink_guard.append(source);
-                            sink_guard.play();
-                        }
-                    }
-                    
-                    // Continue streaming more chunks
-                    buffer.clear();
-                    while let Some(chunk_result) = stream.next().await {
-                        if let Ok(chunk) = chunk_result {
-                            buffer.extend_from_slice(&chunk);
-                            if buffer.len() >= 65536 { // 64KB chunks
-                                let cursor = Cursor::new(buffer.clone());
-                                if let Ok(source) = Decoder::new(cursor) {
-                                    if let Ok(sink_guard) = sink.lock() {
-                                        sink_guard.append(source);
-                                    }
-                                }
-                                buffer.clear();
-                            }
+
+            let mut reason = String::new();
+            for (attempt, stream) in streams.into_iter().enumerate() {
+                match open(&client, &stream, &outlets).await {
+                    Ok(opened) => {
+                        let _ = outlets.events.send(PlayerEvent::Playing { station: station.clone() });
+                        if attempt > 0 {
+                            let _ = outlets.events.send(PlayerEvent::FellBack { station, stream });
                         }
+                        opened.play(&sink, &tap, &paused, &outlets).await;
+                        return;
                     }
+                    Err(e) => reason = e.to_string(),
                 }
             }
+            let _ = outlets.events.send(PlayerEvent::Unavailable { station: station.clone(), reason });
+            // Nothing plays after all, unless another play (even of this
+            // station) took over meanwhile.
+            let cleared = station_ref
+                .lock()
+                .map(|mut current| plays.load(Ordering::SeqCst) == play && current.take().is_some())
+                .unwrap_or(false);
+            if cleared {
+                let _ = outlets.events.send(PlayerEvent::Stopped);
+            }
         });
-        
+
         if let Ok(mut current) = handle_ref.lock() {
             *current = Some(handle);
         }
-        
+
         Ok(())
     }
 
+    /// Moves to the next (or previous) favorite station, wrapping around the
+    /// list. Returns the station now playing, or `None` with no favorites.
+    pub fn skip_favorite(&self, forward: bool) -> Result<Option<StationInfo>> {
+        let favorites = Favorites::load().unwrap_or_default();
+        let current = self.status().station.map(|s| s.id);
+        let next = match favorites.station_after(current.as_deref(), forward) {
+            Some(favorite) => StationInfo {
+                id: favorite.id.clone(),
+                title: favorite.title.clone(),
+                country: favorite.country.clone(),
+            },
+            None => return Ok(None),
+        };
+        self.play_station(next.clone())?;
+        Ok(Some(next))
+    }
+
     pub fn pause(&self) {
         if let Ok(sink) = self.sink.lock() {
             sink.pause();
         }
+        self.paused.store(true, Ordering::Relaxed);
+        let _ = self.events.send(PlayerEvent::Paused);
     }
 
     pub fn resume(&self) {
         if let Ok(sink) = self.sink.lock() {
             sink.play();
         }
+        self.paused.store(false, Ordering::Relaxed);
+        let _ = self.events.send(PlayerEvent::Resumed);
     }
 
     pub fn stop(&self) {
@@ -103,15 +326,255 @@ impl AudioPlayer {
                 h.abort();
             }
         }
-        
+
         // Stop and clear sink
         if let Ok(sink) = self.sink.lock() {
             sink.stop();
             sink.clear();
         }
+
+        self.tap.clear();
+
+        if let Ok(mut track) = self.track.lock() {
+            *track = None;
+        }
+        if let Ok(mut content_type) = self.content_type.lock() {
+            *content_type = None;
+        }
+
+        let was_playing = self
+            .station
+            .lock()
+            .map(|mut station| station.take().is_some())
+            .unwrap_or(false);
+        if was_playing {
+            let _ = self.events.send(PlayerEvent::Stopped);
+        }
     }
 
     pub fn is_paused(&self) -> bool {
-        self.sink.lock().map(|s| s.is_paused()).unwrap_or(false)
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.sink
+            .lock()
+            .map(|s| (s.volume() * 100.0).round() as u8)
+            .unwrap_or(100)
     }
-}
\ No newline at end of file
+
+    /// Sets the output volume in percent, clamped to 0-100.
+    pub fn set_volume(&self, volume: u8) {
+        let volume = volume.min(100);
+        if let Ok(sink) = self.sink.lock() {
+            sink.set_volume(volume as f32 / 100.0);
+        }
+        let _ = self.events.send(PlayerEvent::VolumeChanged { volume });
+    }
+
+    pub fn status(&self) -> PlayerStatus {
+        let station = self.station.lock().ok().and_then(|s| s.clone());
+        let state = match (&station, self.is_paused()) {
+            (None, _) => PlaybackState::Stopped,
+            (Some(_), true) => PlaybackState::Paused,
+            (Some(_), false) => PlaybackState::Playing,
+        };
+        PlayerStatus {
+            state,
+            station,
+            track: self.track.lock().ok().and_then(|t| t.clone()),
+            volume: self.volume(),
+            buffered: self.sink.lock().map(|s| s.len()).unwrap_or(0),
+            received: self.bytes_received(),
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+        self.events.subscribe()
+    }
+
+    /// The encoded stream as received, across station changes.
+    pub fn subscribe_stream(&self) -> broadcast::Receiver<StreamChunk> {
+        self.chunks.subscribe()
+    }
+
+    /// Content type of the current upstream stream, once connected.
+    pub fn content_type(&self) -> Option<String> {
+        self.content_type.lock().ok().and_then(|c| c.clone())
+    }
+
+    /// Total bytes received from upstream; it stops growing when a stream stalls.
+    pub fn bytes_received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Recently played samples, for level meters and spectrum analysis.
+    pub fn tap(&self) -> Arc<SampleTap> {
+        Arc::clone(&self.tap)
+    }
+}
+
+impl StreamOutlets {
+    fn start(&self, content_type: String) {
+        if let Ok(mut current) = self.content_type.lock() {
+            *current = Some(content_type.clone());
+        }
+        let _ = self.chunks.send(StreamChunk::Started { content_type });
+    }
+
+    /// Appends a network chunk's audio to `buffer`, relaying it and publishing
+    /// any new ICY track title.
+    fn ingest(&self, chunk: &[u8], icy: &mut Option<IcyReader>, buffer: &mut Vec<u8>) {
+        let start = buffer.len();
+        let title = self.read(chunk, icy, buffer);
+        self.publish(&buffer[start..], title);
+    }
+
+    /// Appends a network chunk's audio to `buffer`, returning any ICY title it carried.
+    fn read(&self, chunk: &[u8], icy: &mut Option<IcyReader>, buffer: &mut Vec<u8>) -> Option<String> {
+        self.received.fetch_add(chunk.len() as u64, Ordering::Relaxed);
+        match icy {
+            Some(icy) => icy.feed(chunk, buffer),
+            None => {
+                buffer.extend_from_slice(chunk);
+                None
+            }
+        }
+    }
+
+    /// Relays `audio` and publishes `title` if it's new.
+    fn publish(&self, audio: &[u8], title: Option<String>) {
+        if self.chunks.receiver_count() > 0 && !audio.is_empty() {
+            let _ = self.chunks.send(StreamChunk::Audio(Bytes::copy_from_slice(audio)));
+        }
+
+        if let Some(title) = title {
+            if let Ok(mut current) = self.track.lock() {
+                if current.as_deref() == Some(title.as_str()) {
+                    return;
+                }
+                *current = Some(title.clone());
+            }
+            let _ = self.events.send(PlayerEvent::TrackChanged { title });
+        }
+    }
+}
+
+/// An upstream stream that connected and decodes, with the audio read so far.
+struct Opened {
+    stream: Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>,
+    icy: Option<IcyReader>,
+    content_type: String,
+    buffer: Vec<u8>,
+    title: Option<String>,
+    source: Decoder<Cursor<Vec<u8>>>,
+}
+
+/// Connects to `stream` (a station ID or URL) and buffers the start of it,
+/// failing on connection errors, non-audio responses and undecodable audio.
+/// Nothing is published until the stream has proven playable.
+async fn open(client: &reqwest::Client, stream: &str, outlets: &StreamOutlets) -> Result<Opened> {
+    let url = if stream.starts_with("http://") || stream.starts_with("https://") {
+        stream.to_string()
+    } else {
+        api::stream_url(stream)
+    };
+    let response = client
+        .get(&url)
+        .header("Icy-MetaData", "1")
+        .send()
+        .await
+        .map_err(|e| anyhow!("{}: connection failed ({})", stream, e))?;
+    if !response.status().is_success() {
+        bail!("{}: server answered {}", stream, response.status());
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("audio/mpeg")
+        .to_string();
+    if !is_audio(&content_type) {
+        bail!("{}: not an audio stream ({})", stream, content_type);
+    }
+    let mut icy = response
+        .headers()
+        .get("icy-metaint")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&metaint| metaint > 0)
+        .map(IcyReader::new);
+    let mut body = Box::pin(response.bytes_stream());
+    let mut buffer = Vec::new();
+    let mut title = None;
+
+    // Collect larger initial buffer for decoder
+    let _ = tokio::time::timeout(BUFFER_TIMEOUT, async {
+        while let Some(chunk_result) = body.next().await {
+            if let Ok(chunk) = chunk_result {
+                title = outlets.read(&chunk, &mut icy, &mut buffer).or(title.take());
+                if buffer.len() >= 131072 { // 128KB initial buffer
+                    break;
+                }
+            }
+        }
+    })
+    .await;
+
+    if buffer.is_empty() {
+        bail!("{}: no audio received", stream);
+    }
+    let source = Decoder::new(Cursor::new(buffer.clone()))
+        .map_err(|_| anyhow!("{}: unsupported codec ({})", stream, content_type))?;
+
+    Ok(Opened {
+        stream: body,
+        icy,
+        content_type,
+        buffer,
+        title,
+        source,
+    })
+}
+
+/// Whether a response's content type could be a playable audio stream.
+/// Playlists and HTML pages, which some stations answer with, are not.
+fn is_audio(content_type: &str) -> bool {
+    let content_type = content_type.trim().to_ascii_lowercase();
+    content_type.starts_with("audio/")
+        || content_type.starts_with("application/ogg")
+        || content_type.starts_with("application/octet-stream")
+}
+
+impl Opened {
+    /// Publishes the stream and plays it until it ends.
+    async fn play(mut self, sink: &Mutex<Sink>, tap: &Arc<SampleTap>, paused: &AtomicBool, outlets: &StreamOutlets) {
+        outlets.start(self.content_type);
+        outlets.publish(&self.buffer, self.title);
+
+        if let Ok(sink_guard) = sink.lock() {
+            sink_guard.append(TappedSource::new(self.source, Arc::clone(tap)));
+            if !paused.load(Ordering::Relaxed) {
+                sink_guard.play();
+            }
+        }
+
+        // Continue streaming more chunks
+        let mut buffer = Vec::new();
+        while let Some(chunk_result) = self.stream.next().await {
+            if let Ok(chunk) = chunk_result {
+                outlets.ingest(&chunk, &mut self.icy, &mut buffer);
+                if buffer.len() >= 65536 { // 64KB chunks
+                    let cursor = Cursor::new(buffer.clone());
+                    if let Ok(source) = Decoder::new(cursor) {
+                        if let Ok(sink_guard) = sink.lock() {
+                            sink_guard.append(TappedSource::new(source, Arc::clone(tap)));
+                        }
+                    }
+                    buffer.clear();
+                }
+            }
+        }
+    }
+}
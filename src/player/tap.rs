@@ -0,0 +1,129 @@
+use rodio::Source;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Number of interleaved samples kept for the visualizer (~0.37s of 44.1kHz stereo).
+const TAP_CAPACITY: usize = 32768;
+
+/// Lock-free ring of the most recently played samples.
+///
+/// The audio thread writes every sample it hands to the output device, the UI
+/// reads a snapshot of the tail whenever it redraws. Samples are stored as the
+/// bit pattern of an `f32` so each slot is a single atomic.
+pub struct SampleTap {
+    samples: Box<[AtomicU32]>,
+    written: AtomicUsize,
+    channels: AtomicUsize,
+    sample_rate: AtomicU32,
+}
+
+impl SampleTap {
+    pub fn new() -> Self {
+        Self {
+            samples: (0..TAP_CAPACITY).map(|_| AtomicU32::new(0)).collect(),
+            written: AtomicUsize::new(0),
+            channels: AtomicUsize::new(2),
+            sample_rate: AtomicU32::new(44100),
+        }
+    }
+
+    fn push(&self, sample: f32) {
+        let index = self.written.fetch_add(1, Ordering::Relaxed) % self.samples.len();
+        self.samples[index].store(sample.to_bits(), Ordering::Relaxed);
+    }
+
+    fn set_format(&self, channels: u16, sample_rate: u32) {
+        self.channels.store(channels.max(1) as usize, Ordering::Relaxed);
+        self.sample_rate.store(sample_rate, Ordering::Relaxed);
+    }
+
+    /// Copies the last `len` interleaved samples into `out`, oldest first.
+    pub fn snapshot(&self, len: usize, out: &mut Vec<f32>) {
+        let len = len.min(self.samples.len());
+        let end = self.written.load(Ordering::Relaxed);
+        let start = end.saturating_sub(len);
+
+        out.clear();
+        out.resize(len - (end - start), 0.0);
+        out.extend((start..end).map(|i| {
+            f32::from_bits(self.samples[i % self.samples.len()].load(Ordering::Relaxed))
+        }));
+    }
+
+    /// Zeroes the buffer so a stopped stream doesn't leave a frozen picture behind.
+    pub fn clear(&self) {
+        for sample in self.samples.iter() {
+            sample.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Total samples played so far; it stops moving when the output starves.
+    pub fn written(&self) -> usize {
+        self.written.load(Ordering::Relaxed)
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels.load(Ordering::Relaxed)
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate.load(Ordering::Relaxed)
+    }
+}
+
+impl Default for SampleTap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Passes samples through unchanged while copying them into a `SampleTap`.
+pub struct TappedSource<S> {
+    inner: S,
+    tap: Arc<SampleTap>,
+}
+
+impl<S> TappedSource<S>
+where
+    S: Source<Item = i16>,
+{
+    pub fn new(inner: S, tap: Arc<SampleTap>) -> Self {
+        tap.set_format(inner.channels(), inner.sample_rate());
+        Self { inner, tap }
+    }
+}
+
+impl<S> Iterator for TappedSource<S>
+where
+    S: Source<Item = i16>,
+{
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        let sample = self.inner.next()?;
+        self.tap.push(sample as f32 / i16::MAX as f32);
+        Some(sample)
+    }
+}
+
+impl<S> Source for TappedSource<S>
+where
+    S: Source<Item = i16>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
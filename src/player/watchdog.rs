@@ -0,0 +1,329 @@
+//! Dead-air detection: notices when a station goes quiet, or stops sending
+//! data while the connection stays open, and optionally moves on to the next
+//! favorite. Detected stations are logged for later review.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{AudioPlayer, DeadAirReason, PlaybackState, PlayerEvent, StationInfo};
+use crate::config::DeadAirConfig;
+
+const LOG_FILE: &str = "dead-air.json";
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Samples analysed per check; a few thousand are plenty for a level.
+const WINDOW: usize = 8192;
+
+pub struct DeadAirOptions {
+    silence: Duration,
+    stall: Duration,
+    /// Linear RMS equivalent of the configured threshold.
+    threshold: f32,
+    auto_skip: bool,
+}
+
+impl DeadAirOptions {
+    /// `None` when detection is disabled.
+    pub fn from_config(config: &DeadAirConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        Some(Self {
+            silence: Duration::from_secs(config.silence_secs),
+            stall: Duration::from_secs(config.stall_secs),
+            threshold: 10f32.powf(config.threshold_db / 20.0),
+            auto_skip: config.auto_skip,
+        })
+    }
+}
+
+/// A station that was detected as dead, as stored in the log.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadAirRecord {
+    pub station: StationInfo,
+    pub reason: DeadAirReason,
+    pub at: DateTime<Utc>,
+    /// Whether playback moved on to another station.
+    pub skipped: bool,
+}
+
+/// What the watchdog has seen of the current station.
+struct Watch {
+    station_id: String,
+    /// Last time audio above the threshold was played.
+    loud: Instant,
+    /// Last time upstream bytes arrived.
+    receiving: Instant,
+    received: u64,
+    written: usize,
+    /// Set once a stream has connected and audio arrives from it; until then
+    /// the player may still be working through the station's fallbacks.
+    armed: bool,
+    /// Already reported; stays quiet until the station recovers or changes.
+    reported: bool,
+}
+
+/// One check's look at the player.
+struct Reading {
+    at: Instant,
+    /// Whether a stream has been opened.
+    connected: bool,
+    /// Upstream bytes received so far.
+    received: u64,
+    /// Samples played so far.
+    written: usize,
+    /// RMS of the latest samples played.
+    level: f32,
+}
+
+impl Watch {
+    fn new(station_id: String, reading: &Reading) -> Self {
+        Self {
+            station_id,
+            loud: reading.at,
+            receiving: reading.at,
+            received: reading.received,
+            written: reading.written,
+            armed: false,
+            reported: false,
+        }
+    }
+
+    /// Takes in a reading. Returns why the station is dead when it newly is.
+    fn check(&mut self, reading: &Reading, options: &DeadAirOptions) -> Option<DeadAirReason> {
+        let flowing = reading.received != self.received;
+        let played = reading.written != self.written;
+        self.received = reading.received;
+        self.written = reading.written;
+
+        if !self.armed {
+            // A stream counts as started once it's open and its first chunk
+            // or samples come through; time spent connecting doesn't count.
+            self.armed = reading.connected && (flowing || played);
+            self.receiving = reading.at;
+            self.loud = reading.at;
+            return None;
+        }
+
+        if flowing {
+            self.receiving = reading.at;
+        }
+        // Level only counts if new samples were played since the last check;
+        // an output that starved is silent whatever the buffer still holds.
+        if played && reading.level >= options.threshold {
+            self.loud = reading.at;
+        }
+
+        let reason = if reading.at - self.receiving >= options.stall {
+            DeadAirReason::Stalled
+        } else if reading.at - self.loud >= options.silence {
+            DeadAirReason::Silence
+        } else {
+            // Recovered: report it again if it goes dead again.
+            self.reported = false;
+            return None;
+        };
+        if self.reported {
+            return None;
+        }
+        self.reported = true;
+        Some(reason)
+    }
+}
+
+/// Checks on `player` once a second.
+pub async fn watch(player: Arc<AudioPlayer>, options: DeadAirOptions) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    let mut watch: Option<Watch> = None;
+    let mut samples = Vec::with_capacity(WINDOW);
+
+    loop {
+        interval.tick().await;
+        let status = player.status();
+        let station = match (status.state, status.station) {
+            (PlaybackState::Playing, Some(station)) => station,
+            // Paused or stopped: start over whenever playback resumes.
+            _ => {
+                watch = None;
+                continue;
+            }
+        };
+
+        player.tap.snapshot(WINDOW, &mut samples);
+        let reading = Reading {
+            at: Instant::now(),
+            connected: player.content_type().is_some(),
+            received: player.bytes_received(),
+            written: player.tap.written(),
+            level: (samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32).sqrt(),
+        };
+        let current = match &mut watch {
+            Some(current) if current.station_id == station.id => current,
+            _ => watch.insert(Watch::new(station.id.clone(), &reading)),
+        };
+        let Some(reason) = current.check(&reading, &options) else {
+            continue;
+        };
+
+        // Report before skipping, so listeners see it ahead of the station change.
+        let _ = player.events.send(PlayerEvent::DeadAir {
+            station: station.clone(),
+            reason,
+            skipping: options.auto_skip,
+        });
+        let skipped = options.auto_skip
+            && matches!(player.skip_favorite(true), Ok(Some(next)) if next.id != station.id);
+        let _ = log(DeadAirRecord {
+            station,
+            reason,
+            at: Utc::now(),
+            skipped,
+        });
+    }
+}
+
+/// Appends to the dead-air log in the working directory.
+fn log(record: DeadAirRecord) -> Result<()> {
+    let mut records: Vec<DeadAirRecord> = if Path::new(LOG_FILE).exists() {
+        serde_json::from_str(&fs::read_to_string(LOG_FILE)?)?
+    } else {
+        Vec::new()
+    };
+    records.push(record);
+    fs::write(LOG_FILE, serde_json::to_string_pretty(&records)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> DeadAirOptions {
+        DeadAirOptions {
+            silence: Duration::from_secs(10),
+            stall: Duration::from_secs(5),
+            threshold: 0.01,
+            auto_skip: true,
+        }
+    }
+
+    /// Feeds a watch one reading a second.
+    struct Feed {
+        watch: Watch,
+        start: Instant,
+        secs: u64,
+        received: u64,
+        written: usize,
+    }
+
+    impl Feed {
+        fn new() -> Self {
+            let start = Instant::now();
+            let reading = Reading { at: start, connected: false, received: 0, written: 0, level: 0.0 };
+            Self { watch: Watch::new("station".to_string(), &reading), start, secs: 0, received: 0, written: 0 }
+        }
+
+        /// The next second's check: whether data arrived, and the level of
+        /// the samples played, if any were.
+        fn tick(&mut self, connected: bool, receiving: bool, played: Option<f32>) -> Option<DeadAirReason> {
+            self.secs += 1;
+            if receiving {
+                self.received += 4096;
+            }
+            if played.is_some() {
+                self.written += 44100;
+            }
+            let reading = Reading {
+                at: self.start + Duration::from_secs(self.secs),
+                connected,
+                received: self.received,
+                written: self.written,
+                level: played.unwrap_or(0.0),
+            };
+            self.watch.check(&reading, &options())
+        }
+
+        /// `count` seconds of a connected stream playing at `level`, or
+        /// sending nothing at all.
+        fn ticks(&mut self, count: usize, level: Option<f32>) -> Vec<Option<DeadAirReason>> {
+            (0..count).map(|_| self.tick(true, level.is_some(), level)).collect()
+        }
+
+        fn armed() -> Self {
+            let mut feed = Self::new();
+            feed.tick(true, true, Some(0.2));
+            assert!(feed.watch.armed);
+            feed
+        }
+    }
+
+    #[test]
+    fn arms_once_a_connected_stream_delivers() {
+        let mut feed = Feed::new();
+        // Working through fallbacks: nothing connects for a long while.
+        for _ in 0..30 {
+            assert_eq!(feed.tick(false, false, None), None);
+        }
+        // Connected, but nothing has come through yet.
+        assert_eq!(feed.tick(true, false, None), None);
+        assert!(!feed.watch.armed);
+        // Samples still draining from a failed stream don't count either.
+        assert_eq!(feed.tick(false, false, Some(0.2)), None);
+        assert!(!feed.watch.armed);
+        assert_eq!(feed.tick(true, true, None), None);
+        assert!(feed.watch.armed);
+        // The timers start from arming.
+        assert!(feed.ticks(4, None).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn detects_silence() {
+        let mut feed = Feed::armed();
+        let verdicts = feed.ticks(10, Some(0.001));
+        assert!(verdicts[..9].iter().all(Option::is_none));
+        assert_eq!(verdicts[9], Some(DeadAirReason::Silence));
+        // Reported once.
+        assert!(feed.ticks(10, Some(0.001)).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn a_quiet_live_stream_isnt_dead() {
+        let mut feed = Feed::armed();
+        assert!(feed.ticks(60, Some(0.011)).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn starved_output_is_silent() {
+        let mut feed = Feed::armed();
+        // Data arrives but nothing new plays; the tap still holds loud samples.
+        let verdicts: Vec<_> = (0..10).map(|_| feed.tick(true, true, None)).collect();
+        assert_eq!(verdicts[9], Some(DeadAirReason::Silence));
+    }
+
+    #[test]
+    fn detects_a_stall() {
+        let mut feed = Feed::armed();
+        // Buffered audio keeps playing while nothing arrives.
+        let verdicts: Vec<_> = (0..5).map(|_| feed.tick(true, false, Some(0.2))).collect();
+        assert!(verdicts[..4].iter().all(Option::is_none));
+        assert_eq!(verdicts[4], Some(DeadAirReason::Stalled));
+        assert!(feed.ticks(10, None).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn resets_once_data_flows_again() {
+        let mut feed = Feed::armed();
+        assert_eq!(feed.ticks(5, None)[4], Some(DeadAirReason::Stalled));
+        assert_eq!(feed.ticks(1, Some(0.2)), [None]);
+        assert!(!feed.watch.reported);
+        assert!(feed.ticks(4, None).iter().all(Option::is_none));
+        assert_eq!(feed.ticks(1, None), [Some(DeadAirReason::Stalled)]);
+
+        assert_eq!(feed.ticks(1, Some(0.2)), [None]);
+        assert_eq!(feed.ticks(10, Some(0.0))[9], Some(DeadAirReason::Silence));
+    }
+}
@@ -4,8 +4,8 @@ use std::fs;
 use std::path::Path;
 
 pub struct UserPreferences {
-    pub countryId: Option<String>,
-    pub favoriteChannl: Option<String>,
+    pub country_id: Option<String>,
+    pub favorite_channel: Option<String>,
 }
 
 pub struct PreferencesStorage {
@@ -25,10 +25,10 @@ impl PreferencesStorage {
             Map::new()
         };
 
-        if let Some(country_id) = user_preferences.countryId {
+        if let Some(country_id) = user_preferences.country_id {
             existing_prefs.insert("countryId".to_string(), Value::String(country_id));
         }
-        if let Some(favorite_channel) = user_preferences.favoriteChannl {
+        if let Some(favorite_channel) = user_preferences.favorite_channel {
             existing_prefs.insert(
                 "favoriteChannl".to_string(),
                 Value::String(favorite_channel),
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SEARCHES_FILE: &str = "searches.json";
+/// Oldest queries are forgotten past this.
+const MAX_QUERIES: usize = 200;
+
+/// Queries searched in the TUI, oldest first, each kept once.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct SearchHistory {
+    pub queries: Vec<String>,
+    /// Where `save` writes: the file loaded from, or the working directory's.
+    #[serde(skip)]
+    file: Option<PathBuf>,
+}
+
+impl SearchHistory {
+    pub fn load() -> Result<Self> {
+        Self::load_from(Path::new(SEARCHES_FILE))
+    }
+
+    fn load_from(path: &Path) -> Result<Self> {
+        let mut history = if path.exists() {
+            let content = fs::read_to_string(path)?;
+            serde_json::from_str(&content)?
+        } else {
+            Self::default()
+        };
+        history.file = Some(path.to_path_buf());
+        Ok(history)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        fs::write(self.file.as_deref().unwrap_or(Path::new(SEARCHES_FILE)), content)?;
+        Ok(())
+    }
+
+    /// Records `query` as the latest search, moving it up if it was searched before.
+    pub fn add(&mut self, query: &str) -> Result<()> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(());
+        }
+        self.queries.retain(|known| !known.eq_ignore_ascii_case(query));
+        self.queries.push(query.to_string());
+        if self.queries.len() > MAX_QUERIES {
+            let excess = self.queries.len() - MAX_QUERIES;
+            self.queries.drain(..excess);
+        }
+        self.save()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// An empty history saved to a file of the test's own.
+    pub(crate) fn history(test: &str) -> (SearchHistory, PathBuf) {
+        let path = std::env::temp_dir().join(format!("termadio-searches-{}-{}.json", std::process::id(), test));
+        let _ = fs::remove_file(&path);
+        (SearchHistory::load_from(&path).unwrap(), path)
+    }
+
+    #[test]
+    fn keeps_each_query_once_latest_last() {
+        let (mut history, path) = history("dedup");
+        for query in ["jazz", " rock ", "JAZZ", "", "  ", "blues"] {
+            history.add(query).unwrap();
+        }
+        assert_eq!(history.queries, ["rock", "JAZZ", "blues"]);
+        assert_eq!(SearchHistory::load_from(&path).unwrap().queries, history.queries);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn forgets_the_oldest_queries_past_the_cap() {
+        let (mut history, path) = history("cap");
+        for i in 0..MAX_QUERIES + 5 {
+            history.add(&format!("query {}", i)).unwrap();
+        }
+        assert_eq!(history.queries.len(), MAX_QUERIES);
+        assert_eq!(history.queries[0], "query 5");
+        assert_eq!(history.queries[MAX_QUERIES - 1], format!("query {}", MAX_QUERIES + 4));
+        // Searching an old query again moves it up rather than growing the list.
+        history.add("query 5").unwrap();
+        assert_eq!(history.queries.len(), MAX_QUERIES);
+        assert_eq!(history.queries[MAX_QUERIES - 1], "query 5");
+        fs::remove_file(path).unwrap();
+    }
+}
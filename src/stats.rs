@@ -0,0 +1,270 @@
+use chrono::{DateTime, Datelike, Duration, Local, Timelike, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::history::{History, HistoryEntry};
+
+/// How many stations, countries and tracks the rankings keep.
+const TOP_LIMIT: usize = 10;
+
+pub const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Period {
+    Today,
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl Period {
+    pub fn next(self) -> Self {
+        match self {
+            Period::Today => Period::Week,
+            Period::Week => Period::Month,
+            Period::Month => Period::Year,
+            Period::Year => Period::All,
+            Period::All => Period::Today,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Period::Today => "today",
+            Period::Week => "last 7 days",
+            Period::Month => "last 30 days",
+            Period::Year => "last 365 days",
+            Period::All => "all time",
+        }
+    }
+
+    /// Start of the period, or `None` for all time.
+    pub fn cutoff(self) -> Option<DateTime<Utc>> {
+        let now = Local::now();
+        let start = match self {
+            Period::Today => now.date_naive().and_hms_opt(0, 0, 0)?.and_local_timezone(Local).earliest()?,
+            Period::Week => now - Duration::days(7),
+            Period::Month => now - Duration::days(30),
+            Period::Year => now - Duration::days(365),
+            Period::All => return None,
+        };
+        Some(start.with_timezone(&Utc))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankedTime {
+    pub name: String,
+    pub minutes: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RankedTrack {
+    pub title: String,
+    pub plays: usize,
+}
+
+/// Listening totals over a period. Times are in minutes; hours and weekdays
+/// use local time, with weekdays starting on Monday.
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub total_minutes: i64,
+    pub sessions: usize,
+    pub top_stations: Vec<RankedTime>,
+    pub top_countries: Vec<RankedTime>,
+    pub top_tracks: Vec<RankedTrack>,
+    pub by_hour: [i64; 24],
+    pub by_weekday: [i64; 7],
+}
+
+impl Stats {
+    /// Stats for `period` from the history file.
+    pub fn load(period: Period) -> anyhow::Result<Self> {
+        let cutoff = period.cutoff();
+        Ok(Self::compute(History::load()?.since(cutoff), cutoff))
+    }
+
+    /// Sums up `entries`, counting only the part of each session after `cutoff`.
+    pub fn compute<'a>(entries: impl Iterator<Item = &'a HistoryEntry>, cutoff: Option<DateTime<Utc>>) -> Self {
+        let mut total = Duration::zero();
+        let mut sessions = 0;
+        let mut stations: HashMap<&str, (&str, Duration)> = HashMap::new();
+        let mut countries: HashMap<&str, Duration> = HashMap::new();
+        let mut tracks: HashMap<&str, usize> = HashMap::new();
+        let mut by_hour = [Duration::zero(); 24];
+        let mut by_weekday = [Duration::zero(); 7];
+
+        for entry in entries {
+            let start = cutoff.map_or(entry.started, |cutoff| entry.started.max(cutoff));
+            if entry.ended <= start {
+                continue;
+            }
+            let listened = entry.ended - start;
+            total += listened;
+            sessions += 1;
+
+            let station = stations
+                .entry(&entry.station.id)
+                .or_insert((&entry.station.title, Duration::zero()));
+            station.1 += listened;
+            if let Some(country) = &entry.station.country {
+                *countries.entry(country).or_insert_with(Duration::zero) += listened;
+            }
+            for track in entry.tracks.iter().filter(|t| t.at >= start) {
+                *tracks.entry(&track.title).or_insert(0) += 1;
+            }
+
+            // Spread the session over the local hours it covers.
+            let end = entry.ended.with_timezone(&Local);
+            let mut cursor = start.with_timezone(&Local);
+            while cursor < end {
+                let next_hour = cursor
+                    .with_minute(0)
+                    .and_then(|t| t.with_second(0))
+                    .and_then(|t| t.with_nanosecond(0))
+                    .map_or(end, |t| t + Duration::hours(1));
+                let slice = next_hour.min(end) - cursor;
+                by_hour[cursor.hour() as usize] += slice;
+                by_weekday[cursor.weekday().num_days_from_monday() as usize] += slice;
+                cursor = next_hour;
+            }
+        }
+
+        let mut top_stations: Vec<_> = stations
+            .into_values()
+            .map(|(title, time)| RankedTime { name: title.to_string(), minutes: time.num_minutes() })
+            .collect();
+        top_stations.sort_by(|a, b| b.minutes.cmp(&a.minutes).then_with(|| a.name.cmp(&b.name)));
+        top_stations.truncate(TOP_LIMIT);
+
+        let mut top_countries: Vec<_> = countries
+            .into_iter()
+            .map(|(name, time)| RankedTime { name: name.to_string(), minutes: time.num_minutes() })
+            .collect();
+        top_countries.sort_by(|a, b| b.minutes.cmp(&a.minutes).then_with(|| a.name.cmp(&b.name)));
+        top_countries.truncate(TOP_LIMIT);
+
+        let mut top_tracks: Vec<_> = tracks
+            .into_iter()
+            .map(|(title, plays)| RankedTrack { title: title.to_string(), plays })
+            .collect();
+        top_tracks.sort_by(|a, b| b.plays.cmp(&a.plays).then_with(|| a.title.cmp(&b.title)));
+        top_tracks.truncate(TOP_LIMIT);
+
+        Self {
+            total_minutes: total.num_minutes(),
+            sessions,
+            top_stations,
+            top_countries,
+            top_tracks,
+            by_hour: by_hour.map(|d| d.num_minutes()),
+            by_weekday: by_weekday.map(|d| d.num_minutes()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::HeardTrack;
+    use crate::player::StationInfo;
+    use chrono::TimeZone;
+
+    fn at(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 2, hour, minute, 0).unwrap()
+    }
+
+    fn session(title: &str, country: &str, from: (u32, u32), to: (u32, u32), tracks: &[(&str, (u32, u32))]) -> HistoryEntry {
+        HistoryEntry {
+            station: StationInfo {
+                id: title.to_lowercase(),
+                title: title.to_string(),
+                country: Some(country.to_string()),
+            },
+            started: at(from.0, from.1),
+            ended: at(to.0, to.1),
+            tracks: tracks
+                .iter()
+                .map(|(title, (hour, minute))| HeardTrack { title: title.to_string(), at: at(*hour, *minute) })
+                .collect(),
+        }
+    }
+
+    fn history() -> History {
+        History {
+            entries: vec![
+                session("Jazz", "France", (10, 0), (11, 0), &[("Song A", (10, 10)), ("Song B", (10, 40))]),
+                session("Rock", "Spain", (12, 0), (12, 30), &[("Song A", (12, 5))]),
+                session("Jazz", "France", (20, 0), (20, 45), &[]),
+            ],
+        }
+    }
+
+    fn ranked(times: &[RankedTime]) -> Vec<(&str, i64)> {
+        times.iter().map(|t| (t.name.as_str(), t.minutes)).collect()
+    }
+
+    #[test]
+    fn totals_and_rankings() {
+        let history = history();
+        let stats = Stats::compute(history.since(None), None);
+        assert_eq!(stats.total_minutes, 135);
+        assert_eq!(stats.sessions, 3);
+        assert_eq!(ranked(&stats.top_stations), [("Jazz", 105), ("Rock", 30)]);
+        assert_eq!(ranked(&stats.top_countries), [("France", 105), ("Spain", 30)]);
+        let tracks: Vec<_> = stats.top_tracks.iter().map(|t| (t.title.as_str(), t.plays)).collect();
+        assert_eq!(tracks, [("Song A", 2), ("Song B", 1)]);
+        // Hours and weekdays are local time, so only their totals are fixed.
+        assert_eq!(stats.by_hour.iter().sum::<i64>(), 135);
+        assert_eq!(stats.by_weekday.iter().sum::<i64>(), 135);
+    }
+
+    #[test]
+    fn counts_only_the_part_after_the_cutoff() {
+        let history = history();
+        let cutoff = Some(at(10, 30));
+        let stats = Stats::compute(history.since(cutoff), cutoff);
+        assert_eq!(stats.total_minutes, 105);
+        assert_eq!(stats.sessions, 3);
+        assert_eq!(ranked(&stats.top_stations), [("Jazz", 75), ("Rock", 30)]);
+        // Song A's first play was before the cutoff.
+        let tracks: Vec<_> = stats.top_tracks.iter().map(|t| (t.title.as_str(), t.plays)).collect();
+        assert_eq!(tracks, [("Song A", 1), ("Song B", 1)]);
+    }
+
+    #[test]
+    fn skips_sessions_before_the_period() {
+        let history = history();
+        let cutoff = Some(at(12, 30));
+        assert_eq!(history.since(cutoff).count(), 2);
+        let stats = Stats::compute(history.since(cutoff), cutoff);
+        assert_eq!(stats.sessions, 1);
+        assert_eq!(ranked(&stats.top_stations), [("Jazz", 45)]);
+        assert!(stats.top_tracks.is_empty());
+    }
+
+    #[test]
+    fn ties_rank_by_name() {
+        let history = History {
+            entries: vec![
+                session("Zulu", "Spain", (9, 0), (9, 30), &[]),
+                session("Alpha", "France", (10, 0), (10, 30), &[]),
+            ],
+        };
+        let stats = Stats::compute(history.since(None), None);
+        assert_eq!(ranked(&stats.top_stations), [("Alpha", 30), ("Zulu", 30)]);
+        assert_eq!(ranked(&stats.top_countries), [("France", 30), ("Spain", 30)]);
+    }
+
+    #[test]
+    fn period_cutoffs() {
+        assert_eq!(Period::All.cutoff(), None);
+        let cutoff = Period::Week.cutoff().unwrap();
+        let week = Utc::now() - cutoff;
+        assert!(week >= Duration::days(7) && week < Duration::days(7) + Duration::minutes(1));
+        let today = Period::Today.cutoff().unwrap().with_timezone(&Local);
+        assert_eq!((today.hour(), today.minute()), (0, 0));
+    }
+}
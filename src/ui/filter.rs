@@ -0,0 +1,152 @@
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::ListItem,
+};
+
+/// One list entry as displayed. Only `label` is matched against the filter;
+/// `prefix` (icons, dates) and `suffix` (details) are shown around it.
+pub struct Row {
+    pub prefix: String,
+    pub label: String,
+    pub suffix: String,
+}
+
+impl Row {
+    pub fn new(prefix: impl Into<String>, label: impl Into<String>, suffix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            label: label.into(),
+            suffix: suffix.into(),
+        }
+    }
+}
+
+/// A row that passes the filter: its position in the underlying list and
+/// the characters of its label that matched.
+pub struct Visible {
+    pub index: usize,
+    pub positions: Vec<usize>,
+}
+
+/// The `/` filter over the current list.
+pub struct Filter {
+    pub query: String,
+    matcher: SkimMatcherV2,
+}
+
+impl Filter {
+    pub fn new() -> Self {
+        Self {
+            query: String::new(),
+            matcher: SkimMatcherV2::default().ignore_case(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty()
+    }
+
+    /// The rows to show, best matches first. Without a query that's every
+    /// row in its original order.
+    pub fn apply(&self, rows: &[Row]) -> Vec<Visible> {
+        if !self.is_active() {
+            return (0..rows.len())
+                .map(|index| Visible { index, positions: Vec::new() })
+                .collect();
+        }
+        let mut matches: Vec<(i64, Visible)> = rows
+            .iter()
+            .enumerate()
+            .filter_map(|(index, row)| {
+                let (score, positions) = self.matcher.fuzzy_indices(&row.label, &self.query)?;
+                Some((score, Visible { index, positions }))
+            })
+            .collect();
+        matches.sort_by(|(a, x), (b, y)| b.cmp(a).then(x.index.cmp(&y.index)));
+        matches.into_iter().map(|(_, visible)| visible).collect()
+    }
+}
+
+/// A list item for `row` with the matched characters in `style`, bolded.
+pub fn highlight(row: &Row, positions: &[usize], style: Style) -> ListItem<'static> {
+    let matched = style.add_modifier(Modifier::BOLD);
+    let mut spans = vec![Span::raw(row.prefix.clone())];
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in row.label.chars().enumerate() {
+        let is_match = positions.contains(&i);
+        if is_match != run_matched && !run.is_empty() {
+            let text = std::mem::take(&mut run);
+            spans.push(if run_matched { Span::styled(text, matched) } else { Span::raw(text) });
+        }
+        run_matched = is_match;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        spans.push(if run_matched { Span::styled(run, matched) } else { Span::raw(run) });
+    }
+    spans.push(Span::raw(row.suffix.clone()));
+    ListItem::new(Line::from(spans))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(labels: &[&str]) -> Vec<Row> {
+        labels.iter().map(|label| Row::new("📻 ", *label, " (France)")).collect()
+    }
+
+    fn filter(query: &str) -> Filter {
+        let mut filter = Filter::new();
+        filter.query = query.to_string();
+        filter
+    }
+
+    fn indices(visible: &[Visible]) -> Vec<usize> {
+        visible.iter().map(|v| v.index).collect()
+    }
+
+    #[test]
+    fn shows_everything_in_order_without_a_query() {
+        let rows = rows(&["Jazz FM", "Rock Radio", "Ambient"]);
+        assert_eq!(indices(&filter("").apply(&rows)), [0, 1, 2]);
+    }
+
+    #[test]
+    fn maps_matches_back_to_their_rows() {
+        let rows = rows(&["Jazz FM", "Rock Radio", "Smooth Jazz", "Ambient"]);
+        let visible = filter("jazz").apply(&rows);
+        assert_eq!(visible.len(), 2);
+        for v in &visible {
+            assert!(rows[v.index].label.contains("Jazz"));
+        }
+        let mut found = indices(&visible);
+        found.sort();
+        assert_eq!(found, [0, 2]);
+    }
+
+    #[test]
+    fn ranks_better_matches_first() {
+        let rows = rows(&["Radio Rock", "Rock", "Radio Paris"]);
+        let visible = filter("rock").apply(&rows);
+        assert_eq!(indices(&visible), [1, 0]);
+    }
+
+    #[test]
+    fn equal_scores_keep_list_order() {
+        let rows = rows(&["Jazz B", "Other", "Jazz A"]);
+        assert_eq!(indices(&filter("jazz").apply(&rows)), [0, 2]);
+    }
+
+    #[test]
+    fn matches_labels_only() {
+        let rows = rows(&["Rock Radio"]);
+        assert!(filter("france").apply(&rows).is_empty());
+        let visible = filter("rad").apply(&rows);
+        assert_eq!(visible[0].positions, [5, 6, 7]);
+    }
+}
@@ -0,0 +1,425 @@
+//! Key bindings for the TUI. Keys are written as in the config file:
+//! `q`, `G`, `ctrl+s`, `enter`, `pagedown`, `f1`...
+
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::config::KeymapConfig;
+
+/// Normal mode runs actions; insert mode types into the focused input.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Normal,
+    Insert,
+}
+
+impl Mode {
+    pub fn label(self) -> &'static str {
+        match self {
+            Mode::Normal => "NORMAL",
+            Mode::Insert => "INSERT",
+        }
+    }
+}
+
+/// Everything a key can be bound to, named as in the config file. The doc
+/// comments double as the descriptions in the help popup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Action {
+    /// Quit
+    Quit,
+    /// Show this help
+    Help,
+    /// Open the command palette
+    Command,
+    /// Start a new search
+    Search,
+    /// Edit the current search query
+    Edit,
+    /// All favorites
+    Favorites,
+    /// Favorite countries
+    Countries,
+    /// Recently played
+    History,
+    /// Listening stats
+    Stats,
+    /// World map
+    Map,
+    /// Move up
+    Up,
+    /// Move down
+    Down,
+    /// Jump to the top of the list
+    Top,
+    /// Jump to the bottom of the list
+    Bottom,
+    /// Move up a page
+    PageUp,
+    /// Move down a page
+    PageDown,
+    /// Jump to the next item starting with the letter typed next
+    Jump,
+    /// Filter the current list
+    Filter,
+    /// Open or play the selected item
+    Select,
+    /// Go back, or cancel a request in progress
+    Back,
+    /// Go forward again after going back
+    Forward,
+    /// Move the map cursor west
+    Left,
+    /// Move the map cursor east
+    Right,
+    /// Zoom the map in
+    ZoomIn,
+    /// Zoom the map out
+    ZoomOut,
+    /// Move focus to the next pane
+    FocusNext,
+    /// Move focus to the previous pane
+    FocusPrevious,
+    /// Add or remove the selected item from favorites
+    ToggleFavorite,
+    /// Move the selected favorite up
+    MoveUp,
+    /// Move the selected favorite down
+    MoveDown,
+    /// Rename the selected favorite
+    Rename,
+    /// Edit the note on the selected favorite
+    Note,
+    /// Bring back the last favorite removed
+    Undo,
+    /// Pause or resume
+    PlayPause,
+    /// Stop playback
+    Stop,
+    /// Volume up
+    VolumeUp,
+    /// Volume down
+    VolumeDown,
+    /// Cycle the visualizer style
+    CycleVisualizer,
+    /// Next stats period
+    NextPeriod,
+}
+
+impl Action {
+    /// The name used in the config file.
+    pub fn name(self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn description(self) -> String {
+        self.to_possible_value()
+            .and_then(|value| value.get_help().map(|help| help.to_string()))
+            .unwrap_or_else(|| self.name())
+    }
+}
+
+/// A key with its modifiers, e.g. `ctrl+s`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    const fn plain(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::NONE)
+    }
+
+    const fn ctrl(c: char) -> Self {
+        Self::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    const fn key(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+
+    /// Normalizes a key event. Shift is already part of a typed character
+    /// (`G` rather than shift+`g`) and of backtab, so it's dropped for those.
+    fn from_event(key: &KeyEvent) -> Self {
+        let mut modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        if let KeyCode::Char(_) | KeyCode::BackTab = key.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self::new(key.code, modifiers)
+    }
+
+    /// Whether the key types text when an input has focus.
+    pub fn is_text(key: &KeyEvent) -> bool {
+        matches!(key.code, KeyCode::Char(_))
+            && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = s.split('+').collect();
+        // "+" and "ctrl++" bind the plus key itself.
+        if s == "+" || s.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        let (key, prefix) = parts.split_last().ok_or_else(|| anyhow!("empty key"))?;
+        for modifier in prefix {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                other => bail!("unknown modifier '{}' in key '{}'", other, s),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::Char(c)
+            }
+            _ => match key.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "tab" => KeyCode::Tab,
+                "backtab" => {
+                    modifiers.remove(KeyModifiers::SHIFT);
+                    KeyCode::BackTab
+                }
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => bail!("unknown key '{}'", s),
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+const DEFAULT_BINDINGS: &[(KeyBinding, Action)] = &[
+    (KeyBinding::plain('q'), Action::Quit),
+    (KeyBinding::plain('?'), Action::Help),
+    (KeyBinding::plain(':'), Action::Command),
+    (KeyBinding::plain('s'), Action::Search),
+    (KeyBinding::ctrl('s'), Action::Search),
+    (KeyBinding::plain('i'), Action::Edit),
+    (KeyBinding::plain('f'), Action::Favorites),
+    (KeyBinding::ctrl('f'), Action::Favorites),
+    (KeyBinding::plain('c'), Action::Countries),
+    (KeyBinding::ctrl('c'), Action::Countries),
+    (KeyBinding::plain('r'), Action::History),
+    (KeyBinding::ctrl('r'), Action::History),
+    (KeyBinding::plain('t'), Action::Stats),
+    (KeyBinding::ctrl('t'), Action::Stats),
+    (KeyBinding::plain('m'), Action::Map),
+    (KeyBinding::key(KeyCode::Up), Action::Up),
+    (KeyBinding::key(KeyCode::Down), Action::Down),
+    (KeyBinding::key(KeyCode::Left), Action::Left),
+    (KeyBinding::key(KeyCode::Right), Action::Right),
+    (KeyBinding::key(KeyCode::PageUp), Action::PageUp),
+    (KeyBinding::key(KeyCode::PageDown), Action::PageDown),
+    (KeyBinding::key(KeyCode::Home), Action::Top),
+    (KeyBinding::key(KeyCode::End), Action::Bottom),
+    (KeyBinding::plain('\''), Action::Jump),
+    (KeyBinding::plain('/'), Action::Filter),
+    (KeyBinding::key(KeyCode::Enter), Action::Select),
+    (KeyBinding::key(KeyCode::Esc), Action::Back),
+    (KeyBinding::key(KeyCode::Backspace), Action::Back),
+    (KeyBinding::new(KeyCode::Left, KeyModifiers::ALT), Action::Back),
+    (KeyBinding::new(KeyCode::Right, KeyModifiers::ALT), Action::Forward),
+    (KeyBinding::key(KeyCode::Tab), Action::FocusNext),
+    (KeyBinding::key(KeyCode::BackTab), Action::FocusPrevious),
+    (KeyBinding::plain('a'), Action::ToggleFavorite),
+    (KeyBinding::new(KeyCode::Up, KeyModifiers::SHIFT), Action::MoveUp),
+    (KeyBinding::new(KeyCode::Down, KeyModifiers::SHIFT), Action::MoveDown),
+    (KeyBinding::plain('n'), Action::Rename),
+    (KeyBinding::plain('N'), Action::Note),
+    (KeyBinding::plain('u'), Action::Undo),
+    (KeyBinding::plain(' '), Action::PlayPause),
+    (KeyBinding::plain('x'), Action::Stop),
+    (KeyBinding::plain('+'), Action::VolumeUp),
+    (KeyBinding::plain('='), Action::VolumeUp),
+    (KeyBinding::plain('-'), Action::VolumeDown),
+    (KeyBinding::plain('z'), Action::ZoomIn),
+    (KeyBinding::plain('Z'), Action::ZoomOut),
+    (KeyBinding::plain('v'), Action::CycleVisualizer),
+    (KeyBinding::plain('p'), Action::NextPeriod),
+];
+
+const VIM_BINDINGS: &[(KeyBinding, Action)] = &[
+    (KeyBinding::plain('j'), Action::Down),
+    (KeyBinding::plain('k'), Action::Up),
+    (KeyBinding::plain('g'), Action::Top),
+    (KeyBinding::plain('G'), Action::Bottom),
+    (KeyBinding::ctrl('u'), Action::PageUp),
+    (KeyBinding::ctrl('d'), Action::PageDown),
+    (KeyBinding::plain('h'), Action::Back),
+    (KeyBinding::plain('l'), Action::Select),
+    (KeyBinding::plain('H'), Action::Back),
+    (KeyBinding::plain('L'), Action::Forward),
+];
+
+/// Maps keys to actions: a preset, then the user's own bindings on top.
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Keymap {
+    pub fn from_config(config: &KeymapConfig) -> Result<Self> {
+        let mut bindings = DEFAULT_BINDINGS.to_vec();
+        match config.preset.as_str() {
+            "default" => {}
+            "vim" => bindings.extend_from_slice(VIM_BINDINGS),
+            other => bail!("unknown keymap preset '{}' (expected 'default' or 'vim')", other),
+        }
+
+        for (key, action) in &config.bindings {
+            let key: KeyBinding = key.parse()?;
+            bindings.retain(|(bound, _)| *bound != key);
+            if action == "none" {
+                continue;
+            }
+            let action = Action::from_str(action, true)
+                .map_err(|_| anyhow!("unknown action '{}' for key '{}'", action, key))?;
+            bindings.push((key, action));
+        }
+        Ok(Self { bindings })
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        let key = KeyBinding::from_event(key);
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)
+    }
+
+    /// The keys bound to `action`, in binding order.
+    pub fn keys(&self, action: Action) -> Vec<KeyBinding> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> KeyBinding {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_modifiers() {
+        assert_eq!(parse("ctrl+x"), KeyBinding::ctrl('x'));
+        assert_eq!(parse("Control+x"), KeyBinding::ctrl('x'));
+        assert_eq!(
+            parse("ctrl+alt+left"),
+            KeyBinding::new(KeyCode::Left, KeyModifiers::CONTROL | KeyModifiers::ALT)
+        );
+        assert_eq!(parse("ctrl++"), KeyBinding::ctrl('+'));
+        // Shift is part of the character already.
+        assert_eq!(parse("shift+g"), KeyBinding::plain('g'));
+        assert_eq!(parse("shift+tab"), KeyBinding::new(KeyCode::Tab, KeyModifiers::SHIFT));
+    }
+
+    #[test]
+    fn parses_named_keys() {
+        assert_eq!(parse("F5"), KeyBinding::key(KeyCode::F(5)));
+        assert_eq!(parse("f12"), KeyBinding::key(KeyCode::F(12)));
+        assert_eq!(parse("space"), KeyBinding::plain(' '));
+        assert_eq!(parse("PageDown"), KeyBinding::key(KeyCode::PageDown));
+        assert_eq!(parse("+"), KeyBinding::plain('+'));
+        assert_eq!(parse("G"), KeyBinding::plain('G'));
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        for key in ["", "ctrl+", "f0", "f13", "hyper+x", "spacebar", "ctrl+shift+nope"] {
+            assert!(key.parse::<KeyBinding>().is_err(), "{:?} parsed", key);
+        }
+    }
+
+    #[test]
+    fn shift_tab_focuses_the_previous_pane() {
+        let keymap = Keymap::from_config(&KeymapConfig::default()).unwrap();
+        let shift_tab = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&shift_tab), Some(Action::FocusPrevious));
+
+        let config = KeymapConfig { bindings: [("backtab".to_string(), "undo".to_string())].into(), ..KeymapConfig::default() };
+        let keymap = Keymap::from_config(&config).unwrap();
+        assert_eq!(keymap.action(&shift_tab), Some(Action::Undo));
+        assert_eq!(parse("shift+backtab"), KeyBinding::key(KeyCode::BackTab));
+    }
+
+    #[test]
+    fn displays_what_it_parses() {
+        for key in ["ctrl+x", "f5", "space", "alt+enter", "shift+tab", "?"] {
+            assert_eq!(parse(key).to_string(), key);
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Moving through the TUI's lists: single steps, pages, the ends, counts
+//! typed before a move (`5j`, `3pagedown`, `12G`) and jumping to an item by
+//! its first letter. Every list view draws through this, so they all move
+//! the same way.
+
+use ratatui::{
+    layout::Rect,
+    widgets::{Block, List, ListItem, ListState},
+    Frame,
+};
+
+use super::theme::Theme;
+
+/// Largest count kept; more digits are ignored.
+const MAX_COUNT: usize = 99_999;
+
+#[derive(Default)]
+pub struct ListView {
+    pub state: ListState,
+    /// Items that fit in the list at the last draw.
+    page: usize,
+    /// Digits typed before a move, like the 5 in `5j`.
+    count: Option<usize>,
+}
+
+impl ListView {
+    /// Adds a typed digit to the count. A leading 0 isn't a count, so it's
+    /// left alone and `false` returned.
+    pub fn push_digit(&mut self, digit: u32) -> bool {
+        if digit == 0 && self.count.is_none() {
+            return false;
+        }
+        let count = self.count.unwrap_or(0) * 10 + digit as usize;
+        self.count = Some(count.min(MAX_COUNT));
+        true
+    }
+
+    /// The count typed so far, to show while it's pending.
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    pub fn clear_count(&mut self) {
+        self.count = None;
+    }
+
+    fn selected(&self) -> usize {
+        self.state.selected().unwrap_or(0)
+    }
+
+    /// Up one item, wrapping to the bottom, or up `count` items stopping at the top.
+    pub fn up(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let selected = self.selected();
+        let index = match self.count.take() {
+            Some(count) => selected.saturating_sub(count),
+            None if selected == 0 => len - 1,
+            None => selected - 1,
+        };
+        self.state.select(Some(index));
+    }
+
+    /// Down one item, wrapping to the top, or down `count` items stopping at the bottom.
+    pub fn down(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let selected = self.selected();
+        let index = match self.count.take() {
+            Some(count) => (selected + count).min(len - 1),
+            None => (selected + 1) % len,
+        };
+        self.state.select(Some(index));
+    }
+
+    /// Up a page (or `count` pages), keeping the selection at the same
+    /// place on screen where the list allows.
+    pub fn page_up(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let distance = self.page.max(1) * self.count.take().unwrap_or(1);
+        let offset = self.state.offset().saturating_sub(distance);
+        let index = self.selected().saturating_sub(distance);
+        *self.state.offset_mut() = offset;
+        self.state.select(Some(index));
+    }
+
+    /// Down a page (or `count` pages), keeping the selection at the same
+    /// place on screen where the list allows.
+    pub fn page_down(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let distance = self.page.max(1) * self.count.take().unwrap_or(1);
+        let last_offset = len.saturating_sub(self.page.max(1));
+        let offset = (self.state.offset() + distance).min(last_offset);
+        let index = (self.selected() + distance).min(len - 1);
+        *self.state.offset_mut() = offset;
+        self.state.select(Some(index));
+    }
+
+    /// The first item, or item number `count`.
+    pub fn top(&mut self, len: usize) {
+        self.go_to(len, 0);
+    }
+
+    /// The last item, or item number `count`.
+    pub fn bottom(&mut self, len: usize) {
+        self.go_to(len, len.saturating_sub(1));
+    }
+
+    fn go_to(&mut self, len: usize, default: usize) {
+        if len == 0 {
+            self.count = None;
+            return;
+        }
+        let index = match self.count.take() {
+            Some(count) => count.clamp(1, len) - 1,
+            None => default,
+        };
+        self.state.select(Some(index));
+    }
+
+    /// Selects the next item whose label starts with `letter`, searching from
+    /// just after the selection and wrapping around, so pressing the same
+    /// letter again steps through them. Returns whether there was one.
+    pub fn jump(&mut self, letter: char, labels: &[impl AsRef<str>]) -> bool {
+        self.count = None;
+        let len = labels.len();
+        if len == 0 {
+            return false;
+        }
+        let start = self.selected() + 1;
+        let found = (0..len).map(|step| (start + step) % len).find(|&index| {
+            labels[index]
+                .as_ref()
+                .trim_start()
+                .chars()
+                .next()
+                .is_some_and(|first| first.to_lowercase().eq(letter.to_lowercase()))
+        });
+        if let Some(index) = found {
+            self.state.select(Some(index));
+        }
+        found.is_some()
+    }
+
+    /// Draws `items` in `block`, with the selection highlighted, and notes the
+    /// page size. Returns the area inside the block, where the items are.
+    pub fn render(&mut self, f: &mut Frame, area: Rect, block: Block, items: Vec<ListItem>, theme: &Theme) -> Rect {
+        let inner = block.inner(area);
+        self.page = inner.height as usize;
+        // Keep the selection on an item when the list shrank under it.
+        let last = items.len().checked_sub(1);
+        if self.state.selected().is_none() || self.state.selected() > last {
+            self.state.select(last.map(|last| self.selected().min(last)));
+        }
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(theme.selected)
+            .highlight_symbol(theme.icons.selection);
+        f.render_stateful_widget(list, area, &mut self.state);
+        inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A list drawn with room for `page` items, at `selected`.
+    fn list(page: usize, selected: usize) -> ListView {
+        let mut list = ListView { page, ..ListView::default() };
+        list.state.select(Some(selected));
+        list
+    }
+
+    fn count(list: &mut ListView, digits: &str) {
+        for digit in digits.chars() {
+            list.push_digit(digit.to_digit(10).unwrap());
+        }
+    }
+
+    #[test]
+    fn single_steps_wrap() {
+        let mut list = list(10, 0);
+        list.up(5);
+        assert_eq!(list.selected(), 4);
+        list.down(5);
+        assert_eq!(list.selected(), 0);
+    }
+
+    #[test]
+    fn counted_steps_stop_at_the_ends() {
+        let mut list = list(10, 2);
+        count(&mut list, "5");
+        list.up(20);
+        assert_eq!(list.selected(), 0);
+        count(&mut list, "30");
+        list.down(20);
+        assert_eq!(list.selected(), 19);
+        // The count is used up by the move.
+        assert_eq!(list.count(), None);
+        list.down(20);
+        assert_eq!(list.selected(), 0);
+    }
+
+    #[test]
+    fn counts_ignore_a_leading_zero_and_cap() {
+        let mut list = list(10, 0);
+        assert!(!list.push_digit(0));
+        assert_eq!(list.count(), None);
+        count(&mut list, "120");
+        assert_eq!(list.count(), Some(120));
+        count(&mut list, "999999");
+        assert_eq!(list.count(), Some(MAX_COUNT));
+    }
+
+    #[test]
+    fn pages_keep_the_selection_in_place() {
+        let mut list = list(10, 3);
+        list.page_down(35);
+        assert_eq!((list.selected(), list.state.offset()), (13, 10));
+        list.page_down(35);
+        list.page_down(35);
+        // The last page is full, and the selection stops at the last item.
+        assert_eq!((list.selected(), list.state.offset()), (33, 25));
+        list.page_down(35);
+        assert_eq!((list.selected(), list.state.offset()), (34, 25));
+        list.page_up(35);
+        assert_eq!((list.selected(), list.state.offset()), (24, 15));
+        count(&mut list, "3");
+        list.page_up(35);
+        assert_eq!((list.selected(), list.state.offset()), (0, 0));
+    }
+
+    #[test]
+    fn home_and_end_go_to_the_ends_or_the_counted_item() {
+        let mut list = list(10, 5);
+        list.bottom(20);
+        assert_eq!(list.selected(), 19);
+        list.top(20);
+        assert_eq!(list.selected(), 0);
+        count(&mut list, "12");
+        list.top(20);
+        assert_eq!(list.selected(), 11);
+        count(&mut list, "50");
+        list.bottom(20);
+        assert_eq!(list.selected(), 19);
+    }
+
+    #[test]
+    fn empty_lists_stay_put() {
+        let mut list = ListView::default();
+        list.down(0);
+        list.page_down(0);
+        count(&mut list, "4");
+        list.bottom(0);
+        assert_eq!(list.state.selected(), None);
+        assert_eq!(list.count(), None);
+        assert!(!list.jump('a', &[] as &[&str]));
+    }
+
+    #[test]
+    fn jumps_by_first_letter() {
+        let labels = ["Jazz FM", "Ambient", " jazz radio", "Rock", "Alt"];
+        let mut list = list(10, 0);
+        assert!(list.jump('j', &labels));
+        assert_eq!(list.selected(), 2);
+        // Again moves on, wrapping around.
+        assert!(list.jump('J', &labels));
+        assert_eq!(list.selected(), 0);
+        assert!(list.jump('a', &labels));
+        assert_eq!(list.selected(), 1);
+        assert!(list.jump('a', &labels));
+        assert_eq!(list.selected(), 4);
+        assert!(!list.jump('z', &labels));
+        assert_eq!(list.selected(), 4);
+    }
+}
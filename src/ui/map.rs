@@ -0,0 +1,392 @@
+//! The world map: every place with stations plotted on ratatui's world map,
+//! a cursor to move and zoom around it, and the stations nearest the cursor,
+//! by place.
+
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{
+        canvas::{Canvas, Map, MapResolution, Points},
+        Paragraph,
+    },
+    Frame,
+};
+use std::collections::{HashMap, HashSet};
+
+use super::theme::Theme;
+use crate::api::{Place, Station};
+
+/// Width of the nearest stations column.
+const LIST_WIDTH: u16 = 38;
+/// Places whose stations are listed, closest first.
+const NEARBY_PLACES: usize = 5;
+/// Stations listed per place; the rest open with the place.
+const STATIONS_PER_PLACE: usize = 5;
+/// Most zoomed in: 360° / 2^7, a few degrees across.
+const MAX_ZOOM: u32 = 7;
+/// Cursor steps across the visible map.
+const STEPS: f64 = 12.0;
+/// Mean Earth radius, for distances.
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Where the map drew what, for mouse handling.
+pub struct MapAreas {
+    pub canvas: Rect,
+    /// List rows and what each one shows.
+    pub rows: Vec<(Rect, Nearby)>,
+}
+
+/// A row of the nearest stations list.
+#[derive(Clone)]
+pub enum Nearby {
+    /// A place, by index.
+    Place(usize),
+    /// One of a place's stations, by place and station index.
+    Station(usize, usize),
+}
+
+pub struct MapView {
+    /// Longitude and latitude at the center of the view.
+    cursor: (f64, f64),
+    zoom: u32,
+    /// Longitude and latitude spans shown at the last draw.
+    spans: (f64, f64),
+    pub places: Vec<Place>,
+    /// Place ID of every station whose place is known, by station ID.
+    pub station_places: HashMap<String, String>,
+    /// Stations of places that were near the cursor, by place ID.
+    pub place_stations: HashMap<String, Vec<Station>>,
+    /// Places whose stations are being loaded.
+    loading: HashSet<String>,
+}
+
+impl MapView {
+    pub fn new() -> Self {
+        Self {
+            cursor: (0.0, 20.0),
+            zoom: 0,
+            spans: (360.0, 180.0),
+            places: Vec::new(),
+            station_places: HashMap::new(),
+            place_stations: HashMap::new(),
+            loading: HashSet::new(),
+        }
+    }
+
+    /// Moves the cursor by `dx`, `dy` steps east and north, a step being a
+    /// fraction of the visible map.
+    pub fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let (lon, lat) = self.cursor;
+        let lon = lon + dx as f64 * self.spans.0 / STEPS;
+        let lat = lat + dy as f64 * self.spans.1 / STEPS;
+        self.set_cursor(lon, lat);
+    }
+
+    /// Puts the cursor at a longitude and latitude, wrapping around the date line.
+    pub fn set_cursor(&mut self, lon: f64, lat: f64) {
+        self.cursor = ((lon + 180.0).rem_euclid(360.0) - 180.0, lat.clamp(-85.0, 85.0));
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.zoom = (self.zoom + 1).min(MAX_ZOOM);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.zoom = self.zoom.saturating_sub(1);
+    }
+
+    /// Indexes of the `count` places closest to the cursor, with their
+    /// distance in kilometers, closest first.
+    pub fn nearest(&self, count: usize) -> Vec<(usize, f64)> {
+        let mut places: Vec<(usize, f64)> = self
+            .places
+            .iter()
+            .enumerate()
+            .map(|(index, place)| (index, distance_km(self.cursor, (place.geo[0], place.geo[1]))))
+            .collect();
+        places.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+        places.truncate(count);
+        places
+    }
+
+    /// IDs of the nearby places whose stations haven't been asked for yet,
+    /// noted as loading.
+    pub fn places_to_load(&mut self) -> Vec<String> {
+        let mut ids = Vec::new();
+        for (index, _) in self.nearest(NEARBY_PLACES) {
+            let id = &self.places[index].id;
+            if !self.place_stations.contains_key(id) && self.loading.insert(id.clone()) {
+                ids.push(id.clone());
+            }
+        }
+        ids
+    }
+
+    /// Keeps a place's stations, or with `None` (it failed) lets it be asked for again.
+    pub fn loaded(&mut self, place_id: &str, stations: Option<Vec<Station>>) {
+        self.loading.remove(place_id);
+        if let Some(stations) = stations {
+            self.place_stations.insert(place_id.to_string(), stations);
+        }
+    }
+
+    /// The station nearest the cursor with its place, or `None` while the
+    /// closest places that might have one are still loading.
+    pub fn nearest_station(&self) -> Option<(&Station, &Place)> {
+        for (index, _) in self.nearest(NEARBY_PLACES) {
+            let place = &self.places[index];
+            match self.place_stations.get(&place.id)?.first() {
+                Some(station) => return Some((station, place)),
+                None => continue,
+            }
+        }
+        None
+    }
+
+    /// The longitude and latitude under a cell of the canvas.
+    pub fn point_at(&self, canvas: Rect, column: u16, row: u16) -> (f64, f64) {
+        let (x_bounds, y_bounds) = self.bounds();
+        let x = (column - canvas.x) as f64 + 0.5;
+        let y = (row - canvas.y) as f64 + 0.5;
+        (
+            x_bounds[0] + x / canvas.width.max(1) as f64 * self.spans.0,
+            y_bounds[1] - y / canvas.height.max(1) as f64 * self.spans.1,
+        )
+    }
+
+    fn bounds(&self) -> ([f64; 2], [f64; 2]) {
+        let (lon, lat) = self.cursor;
+        let (width, height) = self.spans;
+        ([lon - width / 2.0, lon + width / 2.0], [lat - height / 2.0, lat + height / 2.0])
+    }
+
+    /// Draws the map with `favorites` (place IDs) and the `playing` place marked,
+    /// and the stations nearest the cursor next to it.
+    pub fn render(
+        &mut self,
+        f: &mut Frame,
+        area: Rect,
+        favorites: &HashSet<String>,
+        playing: Option<&str>,
+        focused: bool,
+        theme: &Theme,
+    ) -> MapAreas {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(LIST_WIDTH)])
+            .split(area);
+
+        let (lon, lat) = self.cursor;
+        let title = format!(
+            "World Map ({}x {} {:.1}{} {:.1}{})",
+            1 << self.zoom,
+            theme.icons.dot,
+            lat.abs(),
+            if lat >= 0.0 { "N" } else { "S" },
+            lon.abs(),
+            if lon >= 0.0 { "E" } else { "W" },
+        );
+        let block = theme.pane(focused).title(title);
+        let canvas_area = block.inner(columns[0]);
+        // Cells are about twice as tall as wide, so a row covers twice the degrees of a column.
+        let width = 360.0 / f64::from(1u32 << self.zoom);
+        let height = width * 2.0 * canvas_area.height as f64 / canvas_area.width.max(1) as f64;
+        self.spans = (width, height);
+        let (x_bounds, y_bounds) = self.bounds();
+
+        let all: Vec<(f64, f64)> = self.places.iter().map(|p| (p.geo[0], p.geo[1])).collect();
+        let marked: Vec<(f64, f64, &str, Style)> = self
+            .places
+            .iter()
+            .filter_map(|place| {
+                let (icon, style) = if playing == Some(place.id.as_str()) {
+                    (theme.icons.playing, theme.good)
+                } else if favorites.contains(place.id.as_str()) {
+                    (theme.icons.favorite, theme.warn)
+                } else {
+                    return None;
+                };
+                Some((place.geo[0], place.geo[1], icon, style))
+            })
+            .collect();
+        let marker = if theme.ascii { Marker::Dot } else { Marker::Braille };
+        let canvas = Canvas::default()
+            .block(block)
+            .marker(marker)
+            .x_bounds(x_bounds)
+            .y_bounds(y_bounds)
+            .paint(|ctx| {
+                ctx.draw(&Map {
+                    color: color(theme.muted),
+                    resolution: MapResolution::High,
+                });
+                ctx.layer();
+                ctx.draw(&Points {
+                    coords: &all,
+                    color: color(theme.accent),
+                });
+                ctx.layer();
+                for (lon, lat, icon, style) in &marked {
+                    ctx.print(*lon, *lat, Span::styled(*icon, *style));
+                }
+                ctx.print(lon, lat, Span::styled("+", theme.highlight.add_modifier(Modifier::BOLD)));
+            });
+        f.render_widget(canvas, columns[0]);
+        if theme.ascii {
+            // There's no ASCII canvas marker; swap its dots for periods.
+            let buffer = f.buffer_mut();
+            for y in canvas_area.top()..canvas_area.bottom() {
+                for x in canvas_area.left()..canvas_area.right() {
+                    let cell = buffer.get_mut(x, y);
+                    if cell.symbol == "•" {
+                        cell.set_symbol(".");
+                    }
+                }
+            }
+        }
+
+        let list_block = theme.block().title("Nearest stations");
+        let list_area = list_block.inner(columns[1]);
+        f.render_widget(list_block, columns[1]);
+        if self.places.is_empty() {
+            f.render_widget(Paragraph::new("No places loaded").style(theme.muted), list_area);
+            return MapAreas { canvas: canvas_area, rows: Vec::new() };
+        }
+        // Each place, then its first few stations, until the list is full.
+        let mut lines = Vec::new();
+        let mut rows = Vec::new();
+        let mut first_station = true;
+        for (index, km) in self.nearest(NEARBY_PLACES) {
+            let place = &self.places[index];
+            let mark = if playing == Some(place.id.as_str()) {
+                format!("{} ", theme.icons.playing)
+            } else if favorites.contains(place.id.as_str()) {
+                format!("{} ", theme.icons.favorite)
+            } else {
+                String::new()
+            };
+            rows.push((lines.len(), Nearby::Place(index)));
+            lines.push(Line::from(vec![
+                Span::raw(mark),
+                Span::styled(format!("{}, {}", place.title, place.country), theme.accent),
+                Span::styled(format!(" {} {} km", theme.icons.dot, km.round()), theme.muted),
+            ]));
+            let Some(stations) = self.place_stations.get(&place.id) else {
+                lines.push(Line::from(Span::styled("  loading stations...", theme.muted)));
+                continue;
+            };
+            for (station_index, station) in stations.iter().take(STATIONS_PER_PLACE).enumerate() {
+                // The first one is what Enter plays.
+                let style = if first_station { theme.selected } else { Style::default() };
+                first_station = false;
+                rows.push((lines.len(), Nearby::Station(index, station_index)));
+                lines.push(Line::from(Span::styled(format!("  {} {}", theme.icons.station, station.title), style)));
+            }
+            let more = stations.len().saturating_sub(STATIONS_PER_PLACE);
+            if stations.is_empty() || more > 0 {
+                let text = if more > 0 { format!("  and {} more", more) } else { "  no stations".to_string() };
+                lines.push(Line::from(Span::styled(text, theme.muted)));
+            }
+        }
+        let rows = rows
+            .into_iter()
+            .filter(|(line, _)| *line < list_area.height as usize)
+            .map(|(line, row)| (Rect::new(list_area.x, list_area.y + line as u16, list_area.width, 1), row))
+            .collect();
+        f.render_widget(Paragraph::new(lines), list_area);
+        MapAreas { canvas: canvas_area, rows }
+    }
+}
+
+/// The foreground of a theme style as a canvas color.
+fn color(style: Style) -> Color {
+    style.fg.unwrap_or(Color::Reset)
+}
+
+/// Great-circle distance between two longitude/latitude points.
+fn distance_km(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (lon1, lat1) = (a.0.to_radians(), a.1.to_radians());
+    let (lon2, lat2) = (b.0.to_radians(), b.1.to_radians());
+    let h = ((lat2 - lat1) / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * ((lon2 - lon1) / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Page;
+
+    fn place(id: &str, lon: f64, lat: f64) -> Place {
+        Place {
+            id: id.to_string(),
+            title: id.to_string(),
+            country: "Country".to_string(),
+            geo: [lon, lat],
+            size: 1,
+        }
+    }
+
+    fn station(id: &str) -> Station {
+        Station {
+            title: id.to_uppercase(),
+            page: Page { url: format!("/listen/{}/{}", id, id) },
+        }
+    }
+
+    /// Lisbon, Madrid, Paris, Rome and Tokyo, with the cursor on Lisbon.
+    fn map() -> MapView {
+        let mut map = MapView::new();
+        map.places = vec![
+            place("ROM", 12.5, 41.9),
+            place("TYO", 139.7, 35.7),
+            place("LIS", -9.14, 38.72),
+            place("PAR", 2.35, 48.86),
+            place("MAD", -3.7, 40.4),
+        ];
+        map.set_cursor(-9.14, 38.72);
+        map
+    }
+
+    #[test]
+    fn sorts_places_by_distance() {
+        let map = map();
+        let nearest = map.nearest(3);
+        let ids: Vec<&str> = nearest.iter().map(|&(index, _)| map.places[index].id.as_str()).collect();
+        assert_eq!(ids, ["LIS", "MAD", "PAR"]);
+        assert!(nearest[0].1 < 1.0);
+        // Lisbon to Madrid is about 500 km.
+        assert!((450.0..550.0).contains(&nearest[1].1));
+    }
+
+    #[test]
+    fn loads_each_nearby_place_once() {
+        let mut map = map();
+        assert_eq!(map.places_to_load(), ["LIS", "MAD", "PAR", "ROM", "TYO"]);
+        assert!(map.places_to_load().is_empty());
+        // A failed place is asked for again.
+        map.loaded("MAD", None);
+        map.loaded("LIS", Some(Vec::new()));
+        assert_eq!(map.places_to_load(), ["MAD"]);
+    }
+
+    #[test]
+    fn nearest_station_waits_for_closer_places() {
+        let mut map = map();
+        map.places_to_load();
+        map.loaded("MAD", Some(vec![station("m1")]));
+        assert!(map.nearest_station().is_none());
+        // Lisbon has no stations, so Madrid's first is the nearest.
+        map.loaded("LIS", Some(Vec::new()));
+        let (station, place) = map.nearest_station().unwrap();
+        assert_eq!((station.title.as_str(), place.id.as_str()), ("M1", "MAD"));
+    }
+
+    #[test]
+    fn cursor_wraps_around_the_date_line() {
+        let mut map = MapView::new();
+        map.set_cursor(190.0, 95.0);
+        assert_eq!(map.cursor, (-170.0, 85.0));
+    }
+}
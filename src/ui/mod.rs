@@ -1,57 +1,270 @@
 use anyhow::Result;
+use chrono::Local;
+use clap::ValueEnum;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        DisableMouseCapture, EnableMouseCapture, Event, EventStream, KeyCode, KeyEvent, KeyEventKind, MouseButton,
+        MouseEvent, MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use futures_util::StreamExt;
 use ratatui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{Clear, List, ListItem, Paragraph},
     Frame, Terminal,
 };
+use futures_util::future::join_all;
+use std::collections::HashSet;
+use std::future::Future;
 use std::io;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
 
-use crate::api::{RadioClient, Hit, Station, Page};
-use crate::player::AudioPlayer;
-use crate::favorites::{Favorites, FavoriteStation, FavoriteCountry};
+mod filter;
+mod keymap;
+mod list;
+mod map;
+mod navigation;
+mod now_playing;
+mod palette;
+mod playback;
+mod search;
+mod stats;
+mod theme;
+mod visualizer;
+
+pub use playback::Playback;
+use filter::{Filter, Row, Visible};
+use keymap::{Action, KeyBinding, Keymap, Mode};
+use list::ListView;
+use map::{MapAreas, MapView, Nearby};
+use navigation::{Navigation, Screen, ScreenData};
+use now_playing::NowPlaying;
+use theme::Theme;
+use search::SearchBox;
+use palette::{Candidates, Command, Palette, COMMANDS, MAX_COMPLETIONS};
+use visualizer::Visualizer;
+use crate::config::{Config, LayoutConfig};
+use crate::api::{CountryPage, RadioClient, Hit, PlacesResponse, SearchResult, Station, Page};
+use crate::player::{PlayerEvent, PlayerStatus, StationInfo};
+use crate::favorites::{Favorites, FavoriteStation, FavoriteCountry, Removed};
+use crate::history::{format_duration, History, HistoryEntry};
+use crate::stats::{Period, Stats};
 
 pub struct App {
     client: RadioClient,
-    player: AudioPlayer,
-    search_input: String,
+    playback: Playback,
+    search: SearchBox,
     search_results: Vec<Hit>,
     stations: Vec<Station>,
     current_view: View,
-    list_state: ListState,
-    current_station: Option<String>,
+    /// The current view's breadcrumb; see `navigation::Screen::label`.
+    view_label: String,
+    navigation: Navigation,
+    list: ListView,
+    /// The jump key was pressed; the next character typed is the letter to jump to.
+    jumping: bool,
+    /// Favorites next to the main pane, on wide enough terminals.
+    sidebar: ListView,
+    layout: LayoutConfig,
+    /// Whether the last frame had room for the sidebar.
+    split: bool,
+    focus: Pane,
+    now_playing: NowPlaying,
     status_message: String,
     favorites: Favorites,
+    /// The favorite waiting for a 'y' to be removed, and its name.
+    pending_removal: Option<(FavoriteEntry, String)>,
+    /// The last favorite removed, for undo.
+    removed: Option<Removed>,
+    history: Vec<HistoryEntry>,
+    stats: Option<Stats>,
+    stats_period: Period,
+    map: MapView,
+    visualizer: Visualizer,
+    keymap: Keymap,
+    theme: Theme,
+    mode: Mode,
+    filter: Filter,
+    sender: mpsc::UnboundedSender<Message>,
+    messages: Option<mpsc::UnboundedReceiver<Message>>,
+    /// The network request in flight, if any; only its result is applied.
+    loading: Option<Loading>,
+    /// A status poll is in flight; polls are skipped until it's back.
+    polling: bool,
+    next_request: u64,
+    should_quit: bool,
+    /// Where the last frame drew tabs and buttons, the list and the
+    /// now-playing panel, for mapping mouse events.
+    clickable: Vec<(Rect, Action)>,
+    list_area: Option<Rect>,
+    sidebar_area: Option<Rect>,
+    map_areas: Option<MapAreas>,
+    panel_area: Rect,
+    /// Time and cell of the last click, to spot double-clicks.
+    last_click: Option<(Instant, (u16, u16))>,
+    show_help: bool,
+    /// The `:` command line, while it's open.
+    palette: Option<Palette>,
+    /// When the sleep timer stops playback.
+    sleep_at: Option<Instant>,
 }
 
-#[derive(PartialEq)]
-enum View {
+/// How often to redraw, for the visualizer and the loading spinner.
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+/// How often the now-playing panel refreshes buffer and bitrate figures.
+const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+/// Longest gap between the clicks of a double-click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+/// Volume change per key press or scroll step, in percent.
+const VOLUME_STEP: i16 = 5;
+/// Where `:export m3u` writes the favorites playlist.
+const M3U_FILE: &str = "favorites.m3u";
+/// How many sessions the recently played view lists.
+const RECENT_LIMIT: usize = 200;
+/// Actions listed in the controls hint, in order. The help popup has the rest.
+const HINTED_ACTIONS: [Action; 6] = [
+    Action::Help,
+    Action::Command,
+    Action::Search,
+    Action::Favorites,
+    Action::PlayPause,
+    Action::Quit,
+];
+/// Header tabs: label, the action a click runs, and the view it lights up for.
+const TABS: [(&str, Action, View); 6] = [
+    ("Search", Action::Edit, View::Search),
+    ("Favorites", Action::Favorites, View::Favorites),
+    ("Countries", Action::Countries, View::FavoriteCountries),
+    ("Recent", Action::History, View::History),
+    ("Stats", Action::Stats, View::Stats),
+    ("Map", Action::Map, View::Map),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum View {
     Search,
     Results,
     Stations,
     Favorites,
     FavoriteCountries,
+    History,
+    Stats,
+    Map,
+}
+
+/// The parts of the screen that take focus, in Tab order. Moves and
+/// selection go to the focused one.
+#[derive(Clone, Copy, PartialEq)]
+enum Pane {
+    Sidebar,
+    Main,
+    /// Up/Down change the volume and Enter pauses or resumes.
+    NowPlaying,
+}
+
+/// A favorite country or station, by ID.
+enum FavoriteEntry {
+    Country(String),
+    Station(String),
+}
+
+/// Everything that reaches the event loop besides terminal input.
+enum Message {
+    Player(PlayerEvent),
+    /// `live` results were searched while typing, and stay under the input.
+    SearchResults { request: u64, query: String, live: bool, result: Result<SearchResult> },
+    CountryStations { request: u64, country_id: String, title: String, result: Result<CountryPage> },
+    /// Search results for `:country <name>`.
+    CountryLookup { request: u64, name: String, result: Result<SearchResult> },
+    Places { request: u64, result: Result<PlacesResponse> },
+    /// Place IDs found for stations, by station ID.
+    StationsPlaced(Vec<(String, String)>),
+    /// Stations of a place near the map cursor.
+    PlaceStations { place_id: String, result: Result<CountryPage> },
+    /// The player's status, polled in the background.
+    Status(Result<PlayerStatus>),
+    /// A playback command finished; what to tell the user about it.
+    Controlled(String),
+}
+
+struct Loading {
+    request: u64,
+    label: String,
+    started: Instant,
+    task: JoinHandle<()>,
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
+    pub async fn new(playback: Playback) -> Result<Self> {
+        let config = Config::load()?;
+        let keymap = Keymap::from_config(&config.keymap)?;
+        let theme = Theme::from_config(&config.theme, Theme::no_color_requested())?;
+        let now_playing = NowPlaying::new(playback.status().await.ok());
+        let (sender, messages) = mpsc::unbounded_channel();
+        let mut events = playback.subscribe();
+        {
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                while let Some(event) = events.recv().await {
+                    if sender.send(Message::Player(event)).is_err() {
+                        return;
+                    }
+                }
+            });
+        }
         Ok(Self {
             client: RadioClient::new(),
-            player: AudioPlayer::new()?,
-            search_input: String::new(),
+            playback,
+            search: SearchBox::new(),
             search_results: Vec::new(),
             stations: Vec::new(),
             current_view: View::Search,
-            list_state: ListState::default(),
-            current_station: None,
-            status_message: "Controls: Ctrl+s=search, Ctrl+f=favorites, Ctrl+c=countries, 'a'=favorite, SPACE=pause/play, 'x'=stop, 'q'=quit".to_string(),
+            view_label: "Search".to_string(),
+            navigation: Navigation::default(),
+            list: ListView::default(),
+            jumping: false,
+            sidebar: ListView::default(),
+            layout: config.layout,
+            split: false,
+            focus: Pane::Main,
+            now_playing,
+            status_message: controls_hint(&keymap),
             favorites: Favorites::load().unwrap_or_default(),
+            pending_removal: None,
+            removed: None,
+            history: Vec::new(),
+            stats: None,
+            stats_period: Period::Week,
+            map: MapView::new(),
+            visualizer: Visualizer::new(),
+            keymap,
+            theme,
+            // The search box has focus at startup, so typing searches right away.
+            mode: Mode::Insert,
+            filter: Filter::new(),
+            sender,
+            messages: Some(messages),
+            loading: None,
+            polling: false,
+            next_request: 0,
+            should_quit: false,
+            clickable: Vec::new(),
+            list_area: None,
+            sidebar_area: None,
+            map_areas: None,
+            panel_area: Rect::default(),
+            last_click: None,
+            show_help: false,
+            palette: None,
+            sleep_at: None,
         })
     }
 
@@ -75,258 +288,1240 @@ impl App {
         result
     }
 
+    /// Redraws after every input, message and tick. Network requests run in
+    /// spawned tasks and report back as messages, so nothing here blocks.
     async fn run_app<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
-        loop {
+        let mut input = EventStream::new();
+        let mut messages = self.messages.take().expect("the event loop runs once");
+        let mut ticks = tokio::time::interval(FRAME_INTERVAL);
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        let mut polls = tokio::time::interval(STATUS_INTERVAL);
+        polls.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        while !self.should_quit {
             terminal.draw(|f| self.ui(f))?;
 
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => return Ok(()),
-                    KeyCode::Char('s') => {
-                        if key.modifiers.contains(KeyModifiers::CONTROL) || self.current_view != View::Search {
-                            self.current_view = View::Search;
-                            self.search_input.clear();
-                        } else if self.current_view == View::Search {
-                            self.search_input.push('s');
+            tokio::select! {
+                _ = ticks.tick() => {
+                    if let Some(query) = self.search.take_due(Instant::now()) {
+                        if self.current_view == View::Search {
+                            self.live_search(query);
                         }
                     }
-                    KeyCode::Char('f') => {
-                        if key.modifiers.contains(KeyModifiers::CONTROL) || self.current_view != View::Search {
-                            self.current_view = View::Favorites;
-                            self.list_state.select(Some(0));
-                        } else if self.current_view == View::Search {
-                            self.search_input.push('f');
-                        }
+                }
+                _ = polls.tick() => {
+                    self.poll_status();
+                    self.check_sleep_timer();
+                }
+                event = input.next() => match event {
+                    Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => self.handle_key(key)?,
+                    Some(Ok(Event::Mouse(mouse))) => self.handle_mouse(mouse)?,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => return Err(e.into()),
+                    None => return Ok(()),
+                },
+                Some(message) = messages.recv() => self.handle_message(message),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
+        // Any key closes the help popup.
+        if self.show_help {
+            self.show_help = false;
+            return Ok(());
+        }
+        if self.palette.is_some() {
+            return self.handle_palette_key(key);
+        }
+        if let Some((entry, name)) = self.pending_removal.take() {
+            if key.code == KeyCode::Char('y') {
+                self.remove_favorite(entry);
+            } else {
+                self.status_message = format!("Kept {} in favorites", name);
+            }
+            return Ok(());
+        }
+        if self.mode == Mode::Insert {
+            // The search view's input is the search box; list views type into the filter.
+            let filtering = self.current_view != View::Search;
+            match key.code {
+                _ if KeyBinding::is_text(&key) => {
+                    if let KeyCode::Char(c) = key.code {
+                        self.input_mut().push(c);
                     }
-                    KeyCode::Char('c') => {
-                        if key.modifiers.contains(KeyModifiers::CONTROL) || self.current_view != View::Search {
-                            self.current_view = View::FavoriteCountries;
-                            self.list_state.select(Some(0));
-                        } else if self.current_view == View::Search {
-                            self.search_input.push('c');
+                    self.input_edited(filtering);
+                    return Ok(());
+                }
+                KeyCode::Backspace => {
+                    self.input_mut().pop();
+                    self.input_edited(filtering);
+                    return Ok(());
+                }
+                KeyCode::Enter => {
+                    self.mode = Mode::Normal;
+                    if !filtering && !self.search.input.trim().is_empty() {
+                        self.search();
+                    }
+                    return Ok(());
+                }
+                KeyCode::Up if !filtering => {
+                    self.search.older();
+                    self.drop_results_if_empty();
+                    return Ok(());
+                }
+                KeyCode::Down if !filtering => {
+                    self.search.newer();
+                    self.drop_results_if_empty();
+                    return Ok(());
+                }
+                KeyCode::Tab if !filtering => {
+                    let countries = self.favorite_country_titles();
+                    if self.search.complete(&countries) {
+                        return Ok(());
+                    }
+                }
+                KeyCode::Esc => {
+                    if !self.cancel_loading() {
+                        if filtering {
+                            self.clear_filter();
                         }
+                        self.mode = Mode::Normal;
                     }
-                    KeyCode::Enter => {
-                        match self.current_view {
-                            View::Search => {
-                                if !self.search_input.is_empty() {
-                                    self.search().await?;
-                                }
-                            }
-                    View::Results => {
-                        if let Some(selected) = self.list_state.selected() {
-                            if let Some(hit) = self.search_results.get(selected) {
-                                if hit.source.result_type == "country" {
-                                    if let Some(url) = &hit.source.url {
-                                        if let Some(country_id) = url.split('/').last() {
-                                            let country_id = country_id.to_string();
-                                            self.load_country_stations(&country_id).await?;
-                                        }
-                                    }
-                                } else if hit.source.result_type == "channel" {
-                                    if let Some(page) = &hit.source.page {
-                                        if let Some(station_id) = page.url.split('/').last() {
-                                            let station_id = station_id.to_string();
-                                            let title = hit.source.title.clone();
-                                            self.play_station(&station_id, &title)?;
-                                        }
-                                    }
+                    return Ok(());
+                }
+                // Other keys, like ctrl+f or the arrows, still run their actions.
+                _ => {}
+            }
+        }
+
+        // After the jump key, any character is the letter to jump to, bound
+        // or not; other keys just cancel the jump.
+        if std::mem::take(&mut self.jumping) {
+            if let KeyCode::Char(c) = key.code {
+                if KeyBinding::is_text(&key) {
+                    self.jump_to(c);
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(action) = self.keymap.action(&key) {
+            self.perform(action)?;
+        } else if let KeyCode::Char(c) = key.code {
+            if KeyBinding::is_text(&key) {
+                self.type_to_list(c);
+            }
+        }
+        Ok(())
+    }
+
+    /// Unbound keys in normal mode: digits make a count for the next move.
+    /// Letters only jump after the jump key, as most of them are bound.
+    fn type_to_list(&mut self, c: char) {
+        if self.focused_len() == 0 {
+            return;
+        }
+        if let Some(digit) = c.to_digit(10) {
+            if self.focused_list().push_digit(digit) {
+                return;
+            }
+        }
+        self.focused_list().clear_count();
+        if c.is_alphabetic() {
+            self.status_message = format!("'{}' isn't bound; {}, then the letter", c, key_hint(&self.keymap, Action::Jump, "jump"));
+        }
+    }
+
+    /// Selects the next item in the focused list that starts with `c`.
+    fn jump_to(&mut self, c: char) {
+        if self.focused_len() == 0 {
+            return;
+        }
+        let labels = if self.focus == Pane::Sidebar {
+            self.sidebar_labels()
+        } else {
+            let rows = self.rows();
+            self.visible().into_iter().map(|v| rows[v.index].label.clone()).collect()
+        };
+        if !self.focused_list().jump(c, &labels) {
+            self.status_message = format!("Nothing starts with '{}'", c);
+        }
+    }
+
+    fn handle_palette_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some(palette) = self.palette.as_mut() else { return Ok(()) };
+        if key.code != KeyCode::Enter {
+            palette.error = None;
+        }
+        match key.code {
+            _ if KeyBinding::is_text(&key) => {
+                if let KeyCode::Char(c) = key.code {
+                    palette.input.push(c);
+                }
+            }
+            // Backspace on an empty line closes the palette.
+            KeyCode::Backspace if palette.input.is_empty() => self.palette = None,
+            KeyCode::Backspace => {
+                palette.input.pop();
+            }
+            KeyCode::Tab => {
+                let candidates = self.candidates();
+                if let Some(palette) = self.palette.as_mut() {
+                    palette.complete(&candidates);
+                }
+            }
+            // A command that doesn't parse stays open to be fixed.
+            KeyCode::Enter => match palette.parse() {
+                Ok(command) => {
+                    self.palette = None;
+                    self.run_command(command)?;
+                }
+                Err(e) => palette.error = Some(e.to_string()),
+            },
+            KeyCode::Esc => self.palette = None,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// What `:play` and `:country` complete from: favorites, then recent stations.
+    fn candidates(&self) -> Candidates {
+        let mut stations: Vec<(String, String)> = Vec::new();
+        let known = self.favorites.stations.iter().map(|s| (&s.id, &s.title));
+        for (id, title) in known.chain(self.history.iter().map(|e| (&e.station.id, &e.station.title))) {
+            if !stations.iter().any(|(known, _)| known == id) {
+                stations.push((id.clone(), title.clone()));
+            }
+        }
+        Candidates {
+            stations,
+            countries: self.favorites.countries.iter().map(|c| c.title.clone()).collect(),
+        }
+    }
+
+    fn run_command(&mut self, command: Command) -> Result<()> {
+        match command {
+            Command::Action(action) => self.perform(action)?,
+            Command::Play(id) => {
+                let favorite = self.favorites.stations.iter().find(|s| s.id == id).map(|s| (s.title.clone(), s.country.clone()));
+                let recent = || {
+                    self.history
+                        .iter()
+                        .find(|e| e.station.id == id)
+                        .map(|e| (e.station.title.clone(), e.station.country.clone()))
+                };
+                let (title, country) = favorite.or_else(recent).unwrap_or_else(|| (id.clone(), None));
+                self.play_station(&id, &title, country)?;
+            }
+            Command::Country(name) => self.open_country(&name),
+            Command::Volume(volume) => self.set_volume(volume),
+            Command::Sleep(Some(duration)) => {
+                self.sleep_at = Some(Instant::now() + duration);
+                let duration = chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::zero());
+                self.status_message = format!("{} Playback stops in {}", self.theme.icons.sleep, format_duration(duration));
+            }
+            Command::Sleep(None) => {
+                self.sleep_at = None;
+                self.status_message = format!("{} Sleep timer off", self.theme.icons.sleep);
+            }
+            Command::Rename(name) => self.rename_favorite(name),
+            Command::Note(note) => self.annotate_favorite(note),
+            Command::ExportM3u => {
+                self.status_message = match self.favorites.export_m3u(M3U_FILE) {
+                    Ok(()) => format!(
+                        "{} Exported {} favorite stations to {}",
+                        self.theme.icons.saved,
+                        self.favorites.stations.len(),
+                        M3U_FILE
+                    ),
+                    Err(e) => format!("Export failed: {}", e),
+                };
+            }
+        }
+        Ok(())
+    }
+
+    /// Asks for the player's status, unless the last poll is still out.
+    fn poll_status(&mut self) {
+        if self.polling {
+            return;
+        }
+        self.polling = true;
+        let playback = self.playback.clone();
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let _ = sender.send(Message::Status(playback.status().await));
+        });
+    }
+
+    /// Runs a playback command in the background, since the daemon may take
+    /// its time to answer, and shows the message it finishes with.
+    fn control<F>(&self, command: impl FnOnce(Playback) -> F)
+    where
+        F: Future<Output = String> + Send + 'static,
+    {
+        let sender = self.sender.clone();
+        let command = command(self.playback.clone());
+        tokio::spawn(async move {
+            let _ = sender.send(Message::Controlled(command.await));
+        });
+    }
+
+    fn check_sleep_timer(&mut self) {
+        if self.sleep_at.is_some_and(|at| Instant::now() >= at) {
+            self.sleep_at = None;
+            let icon = self.theme.icons.sleep;
+            self.control(|playback| async move {
+                match playback.stop().await {
+                    Ok(()) => format!("{} Sleep timer stopped playback", icon),
+                    Err(e) => format!("Playback control failed: {}", e),
+                }
+            });
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<()> {
+        if self.show_help {
+            if let MouseEventKind::Down(_) = mouse.kind {
+                self.show_help = false;
+            }
+            return Ok(());
+        }
+        let (column, row) = (mouse.column, mouse.row);
+        let inside = |area: Rect| area.x <= column && column < area.right() && area.y <= row && row < area.bottom();
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(&(_, action)) = self.clickable.iter().find(|(area, _)| inside(*area)) {
+                    return self.perform(action);
+                }
+                // Two quick clicks on the same cell open or play what's there.
+                let double = self
+                    .last_click
+                    .is_some_and(|(at, cell)| cell == (column, row) && at.elapsed() <= DOUBLE_CLICK);
+                self.last_click = if double { None } else { Some((Instant::now(), (column, row))) };
+
+                // On the map a click moves the cursor there, or to a listed
+                // place, and a listed station plays.
+                let map_click = self.map_areas.as_ref().and_then(|areas| {
+                    let nearby = areas.rows.iter().find(|(area, _)| inside(*area)).map(|(_, nearby)| nearby.clone());
+                    (nearby.is_some() || inside(areas.canvas)).then_some((nearby, areas.canvas))
+                });
+                if let Some((nearby, canvas)) = map_click {
+                    self.focus = Pane::Main;
+                    if let Some(Nearby::Station(place, station)) = nearby {
+                        return self.play_nearby(place, station);
+                    }
+                    // The first click already moved the cursor; the list has re-sorted since.
+                    if double {
+                        return self.perform(Action::Select);
+                    }
+                    let (lon, lat) = match nearby {
+                        Some(Nearby::Place(index)) => (self.map.places[index].geo[0], self.map.places[index].geo[1]),
+                        _ => self.map.point_at(canvas, column, row),
+                    };
+                    self.map.set_cursor(lon, lat);
+                    self.load_nearby_stations();
+                    return Ok(());
+                }
+                // A click focuses the pane under it, and selects a list item.
+                let (pane, list) = if let Some(area) = self.sidebar_area.filter(|area| inside(*area)) {
+                    (Pane::Sidebar, area)
+                } else if let Some(area) = self.list_area.filter(|area| inside(*area)) {
+                    (Pane::Main, area)
+                } else {
+                    if inside(self.panel_area) && self.now_playing.station.is_some() {
+                        self.focus = Pane::NowPlaying;
+                    }
+                    return Ok(());
+                };
+                self.focus = pane;
+                let index = self.focused_list().state.offset() + (row - list.y) as usize;
+                if index >= self.focused_len() {
+                    return Ok(());
+                }
+                self.focused_list().state.select(Some(index));
+                if double {
+                    return self.perform(Action::Select);
+                }
+            }
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                let up = mouse.kind == MouseEventKind::ScrollUp;
+                if inside(self.panel_area) && self.now_playing.station.is_some() {
+                    return self.perform(if up { Action::VolumeUp } else { Action::VolumeDown });
+                }
+                if self.map_areas.as_ref().is_some_and(|areas| inside(areas.canvas)) {
+                    self.focus = Pane::Main;
+                    return self.perform(if up { Action::ZoomIn } else { Action::ZoomOut });
+                }
+                if self.sidebar_area.is_some_and(inside) {
+                    self.focus = Pane::Sidebar;
+                } else if self.list_area.is_some_and(inside) {
+                    self.focus = Pane::Main;
+                } else {
+                    return Ok(());
+                }
+                return self.perform(if up { Action::Up } else { Action::Down });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn perform(&mut self, action: Action) -> Result<()> {
+        let action = match (self.focus, action) {
+            (Pane::NowPlaying, Action::Up) => Action::VolumeUp,
+            (Pane::NowPlaying, Action::Down) => Action::VolumeDown,
+            (Pane::NowPlaying, Action::Select) => Action::PlayPause,
+            _ => action,
+        };
+        let moves = matches!(
+            action,
+            Action::Up | Action::Down | Action::PageUp | Action::PageDown | Action::Top | Action::Bottom
+        );
+        // Anything but moving through the list ends typing.
+        if !moves && !matches!(action, Action::Search | Action::Edit | Action::Filter) {
+            self.mode = Mode::Normal;
+        }
+        // A count only applies to the move right after it.
+        if !moves {
+            self.focused_list().clear_count();
+        }
+        match action {
+            Action::Quit => self.should_quit = true,
+            Action::Help => self.show_help = true,
+            Action::Command => self.palette = Some(Palette::default()),
+            Action::Search => {
+                self.navigate(View::Search, "Search");
+                self.search.clear();
+                self.search_results.clear();
+                self.mode = Mode::Insert;
+            }
+            Action::Edit => {
+                self.navigate(View::Search, "Search");
+                if self.search_results.is_empty() {
+                    self.search.edited();
+                }
+                self.mode = Mode::Insert;
+            }
+            Action::Filter => {
+                if matches!(self.current_view, View::Search | View::Stats | View::Map) {
+                    self.status_message = "Nothing to filter in this view".to_string();
+                } else {
+                    self.focus = Pane::Main;
+                    self.mode = Mode::Insert;
+                }
+            }
+            Action::Favorites => self.navigate(View::Favorites, "Favorites"),
+            Action::Countries => self.navigate(View::FavoriteCountries, "Countries"),
+            Action::History => self.load_history(),
+            Action::Stats => self.load_stats(),
+            Action::Map => self.open_map(),
+            Action::Up if self.on_map() => self.move_map_cursor(0, 1),
+            Action::Down if self.on_map() => self.move_map_cursor(0, -1),
+            Action::Left if self.on_map() => self.move_map_cursor(-1, 0),
+            Action::Right if self.on_map() => self.move_map_cursor(1, 0),
+            Action::ZoomIn if self.on_map() => self.map.zoom_in(),
+            Action::ZoomOut if self.on_map() => self.map.zoom_out(),
+            Action::Left | Action::Right | Action::ZoomIn | Action::ZoomOut => {}
+            Action::Up => {
+                let len = self.focused_len();
+                self.focused_list().up(len);
+            }
+            Action::Down => {
+                let len = self.focused_len();
+                self.focused_list().down(len);
+            }
+            Action::PageUp => {
+                let len = self.focused_len();
+                self.focused_list().page_up(len);
+            }
+            Action::PageDown => {
+                let len = self.focused_len();
+                self.focused_list().page_down(len);
+            }
+            Action::Top => {
+                let len = self.focused_len();
+                self.focused_list().top(len);
+            }
+            Action::Bottom => {
+                let len = self.focused_len();
+                self.focused_list().bottom(len);
+            }
+            Action::Jump => self.jumping = true,
+            Action::Select if self.focus == Pane::Sidebar => {
+                if let Some(selected) = self.sidebar.state.selected() {
+                    self.select_favorite(selected)?;
+                }
+            }
+            Action::Select => self.select()?,
+            Action::Back => {
+                if self.cancel_loading() {
+                    return Ok(());
+                }
+                if self.filter.is_active() {
+                    self.clear_filter();
+                    return Ok(());
+                }
+                if self.navigation.can_go_back() {
+                    let current = self.take_screen();
+                    let previous = self.navigation.back(current);
+                    self.restore_screen(previous);
+                }
+            }
+            Action::Forward => {
+                if self.navigation.can_go_forward() {
+                    let current = self.take_screen();
+                    let next = self.navigation.forward(current);
+                    self.restore_screen(next);
+                }
+            }
+            Action::ToggleFavorite if self.focus == Pane::Sidebar => {
+                if let Some(selected) = self.sidebar.state.selected() {
+                    self.ask_removal(selected);
+                }
+            }
+            Action::ToggleFavorite => self.add_to_favorites(),
+            Action::MoveUp => self.move_favorite(true),
+            Action::MoveDown => self.move_favorite(false),
+            Action::Rename => self.edit_favorite("rename"),
+            Action::Note => self.edit_favorite("note"),
+            Action::Undo => self.undo_removal(),
+            Action::FocusNext => self.cycle_focus(true),
+            Action::FocusPrevious => self.cycle_focus(false),
+            Action::PlayPause => {
+                let icons = self.theme.icons;
+                self.control(|playback| async move {
+                    match playback.toggle_pause().await {
+                        Ok(true) => format!("{} Paused playback", icons.paused),
+                        Ok(false) => format!("{} Resumed playback", icons.playing),
+                        Err(e) => format!("Playback control failed: {}", e),
+                    }
+                });
+            }
+            Action::Stop => {
+                let icon = self.theme.icons.stopped;
+                self.control(|playback| async move {
+                    match playback.stop().await {
+                        Ok(()) => format!("{} Stopped playback", icon),
+                        Err(e) => format!("Playback control failed: {}", e),
+                    }
+                });
+            }
+            Action::VolumeUp => self.change_volume(VOLUME_STEP),
+            Action::VolumeDown => self.change_volume(-VOLUME_STEP),
+            Action::CycleVisualizer => {
+                self.visualizer.toggle_style();
+                self.status_message = format!("Visualizer: {}", self.visualizer.style().label());
+            }
+            Action::NextPeriod => {
+                if self.current_view == View::Stats {
+                    self.stats_period = self.stats_period.next();
+                    self.load_stats();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn change_volume(&mut self, delta: i16) {
+        self.set_volume((self.now_playing.volume() as i16 + delta).clamp(0, 100) as u8);
+    }
+
+    fn set_volume(&mut self, volume: u8) {
+        // Apply it now so quick repeats build on it before the event arrives;
+        // if the command fails, the next status poll puts it back.
+        self.now_playing.apply(&PlayerEvent::VolumeChanged { volume });
+        let icon = self.theme.icons.volume;
+        self.control(|playback| async move {
+            match playback.set_volume(volume).await {
+                Ok(()) => format!("{} Volume: {}%", icon, volume),
+                Err(e) => format!("Playback control failed: {}", e),
+            }
+        });
+    }
+
+    /// Opens or plays the selected item of the current view.
+    fn select(&mut self) -> Result<()> {
+        match self.current_view {
+            View::Search if self.search_results.is_empty() => {
+                if !self.search.input.trim().is_empty() {
+                    self.search();
+                }
+            }
+            View::Search | View::Results => {
+                if let Some(selected) = self.selected_index() {
+                    if let Some(hit) = self.search_results.get(selected) {
+                        if hit.source.result_type == "country" {
+                            if let Some(url) = &hit.source.url {
+                                if let Some(country_id) = url.split('/').next_back() {
+                                    let country_id = country_id.to_string();
+                                    let title = hit.source.title.clone();
+                                    self.load_country_stations(&country_id, &title);
                                 }
                             }
-                        }
-                    }
-                    View::Stations => {
-                        if let Some(selected) = self.list_state.selected() {
-                            if let Some(station) = self.stations.get(selected) {
-                                if let Some(station_id) = station.page.url.split('/').last() {
+                        } else if hit.source.result_type == "channel" {
+                            if let Some(page) = &hit.source.page {
+                                if let Some(station_id) = page.url.split('/').next_back() {
                                     let station_id = station_id.to_string();
-                                    let title = station.title.clone();
-                                    self.play_station(&station_id, &title)?;
+                                    let title = hit.source.title.clone();
+                                    let country = hit.source.country.as_ref().map(|c| c.title.clone());
+                                    self.play_station(&station_id, &title, country)?;
                                 }
                             }
                         }
                     }
-                    View::Favorites => {
-                        if let Some(selected) = self.list_state.selected() {
-                            let countries_count = self.favorites.countries.len();
-                            if selected < countries_count {
-                                // Selected a country - load its stations
-                                if let Some(country) = self.favorites.countries.get(selected) {
-                                    let country_id = country.id.clone();
-                                    self.load_country_stations(&country_id).await?;
-                                }
-                            } else {
-                                // Selected a station - play it
-                                let station_index = selected - countries_count;
-                                if let Some(station) = self.favorites.stations.get(station_index) {
-                                    let station_id = station.id.clone();
-                                    let station_title = station.title.clone();
-                                    self.play_station(&station_id, &station_title)?;
-                                }
-                            }
+                }
+            }
+            View::Stations => {
+                if let Some(selected) = self.selected_index() {
+                    if let Some(station) = self.stations.get(selected) {
+                        if let Some(station_id) = station.page.url.split('/').next_back() {
+                            let station_id = station_id.to_string();
+                            let title = station.title.clone();
+                            self.play_station(&station_id, &title, None)?;
                         }
                     }
-                    View::FavoriteCountries => {
-                        if let Some(selected) = self.list_state.selected() {
-                            if let Some(country) = self.favorites.countries.get(selected) {
-                                let country_id = country.id.clone();
-                                self.load_country_stations(&country_id).await?;
-                            }
-                        }
+                }
+            }
+            View::Favorites => {
+                if let Some(selected) = self.selected_index() {
+                    self.select_favorite(selected)?;
+                }
+            }
+            View::FavoriteCountries => {
+                if let Some(selected) = self.selected_index() {
+                    if let Some(country) = self.favorites.countries.get(selected) {
+                        let (country_id, title) = (country.id.clone(), country.title.clone());
+                        self.load_country_stations(&country_id, &title);
                     }
                 }
             }
-            KeyCode::Up => {
-                if self.current_view != View::Search {
-                    let len = match self.current_view {
-                        View::Results => self.search_results.len(),
-                        View::Stations => self.stations.len(),
-                        View::Favorites => self.favorites.countries.len() + self.favorites.stations.len(),
-                        View::FavoriteCountries => self.favorites.countries.len(),
-                        _ => 0,
-                    };
-                    if len > 0 {
-                        let selected = self.list_state.selected().unwrap_or(0);
-                        self.list_state.select(Some(if selected == 0 { len - 1 } else { selected - 1 }));
+            View::History => {
+                if let Some(selected) = self.selected_index() {
+                    if let Some(entry) = self.history.get(selected) {
+                        let station = entry.station.clone();
+                        self.play_station(&station.id, &station.title, station.country)?;
                     }
                 }
             }
-            KeyCode::Down => {
-                if self.current_view != View::Search {
-                    let len = match self.current_view {
-                        View::Results => self.search_results.len(),
-                        View::Stations => self.stations.len(),
-                        View::Favorites => self.favorites.countries.len() + self.favorites.stations.len(),
-                        View::FavoriteCountries => self.favorites.countries.len(),
-                        _ => 0,
-                    };
-                    if len > 0 {
-                        let selected = self.list_state.selected().unwrap_or(0);
-                        self.list_state.select(Some((selected + 1) % len));
+            View::Map => match self.map.nearest_station() {
+                Some((station, place)) => {
+                    let (title, country) = (station.title.clone(), place.country.clone());
+                    if let Some(station_id) = station.page.url.split('/').next_back() {
+                        let station_id = station_id.to_string();
+                        self.play_station(&station_id, &title, Some(country))?;
                     }
                 }
+                None => self.status_message = "Stations near the cursor are still loading".to_string(),
+            },
+            View::Stats => {}
+        }
+        Ok(())
+    }
+
+    fn move_map_cursor(&mut self, dx: i32, dy: i32) {
+        self.map.move_cursor(dx, dy);
+        self.load_nearby_stations();
+    }
+
+    /// Plays a station listed next to the map, by place and station index.
+    fn play_nearby(&mut self, place: usize, station: usize) -> Result<()> {
+        let place = &self.map.places[place];
+        let Some(station) = self.map.place_stations.get(&place.id).and_then(|stations| stations.get(station)) else {
+            return Ok(());
+        };
+        let (title, country) = (station.title.clone(), place.country.clone());
+        if let Some(station_id) = station.page.url.split('/').next_back() {
+            let station_id = station_id.to_string();
+            self.play_station(&station_id, &title, Some(country))?;
+        }
+        Ok(())
+    }
+
+    /// Whether moves go to the map cursor rather than a list.
+    fn on_map(&self) -> bool {
+        self.current_view == View::Map && self.focus == Pane::Main
+    }
+
+    /// Panes that can take focus in the current layout, in Tab order.
+    fn panes(&self) -> Vec<Pane> {
+        let mut panes = Vec::new();
+        if self.split {
+            panes.push(Pane::Sidebar);
+        }
+        panes.push(Pane::Main);
+        if self.now_playing.station.is_some() {
+            panes.push(Pane::NowPlaying);
+        }
+        panes
+    }
+
+    fn cycle_focus(&mut self, forward: bool) {
+        let panes = self.panes();
+        let current = panes.iter().position(|pane| *pane == self.focus).unwrap_or(0);
+        let next = if forward { current + 1 } else { current + panes.len() - 1 };
+        self.focus = panes[next % panes.len()];
+    }
+
+    /// The list that moves and selects: the sidebar's while it has focus.
+    fn focused_list(&mut self) -> &mut ListView {
+        if self.focus == Pane::Sidebar {
+            &mut self.sidebar
+        } else {
+            &mut self.list
+        }
+    }
+
+    fn focused_len(&self) -> usize {
+        if self.focus == Pane::Sidebar {
+            self.sidebar_labels().len()
+        } else {
+            self.list_len()
+        }
+    }
+
+    /// Titles in the sidebar: favorite countries, then stations.
+    fn sidebar_labels(&self) -> Vec<String> {
+        let countries = self.favorites.countries.iter().map(|country| country.display_name().to_string());
+        let stations = self.favorites.stations.iter().map(|station| station.display_name().to_string());
+        countries.chain(stations).collect()
+    }
+
+    /// Number of items shown in the current view's list, after filtering.
+    fn list_len(&self) -> usize {
+        self.visible().len()
+    }
+
+    /// Opens `view`, unfiltered at the top, keeping the current screen to
+    /// go back to. Opening the screen that's already shown just starts it over.
+    fn navigate(&mut self, view: View, label: &str) {
+        self.focus = Pane::Main;
+        if view == self.current_view && label == self.view_label {
+            self.clear_filter();
+            return;
+        }
+        let current = self.take_screen();
+        self.navigation.visit(current);
+        self.current_view = view;
+        self.view_label = label.to_string();
+        self.list.state.select(Some(0));
+    }
+
+    /// Moves the current screen, with the data only it shows, out of the app.
+    fn take_screen(&mut self) -> Screen {
+        let data = match self.current_view {
+            View::Search | View::Results => ScreenData::Results(std::mem::take(&mut self.search_results)),
+            View::Stations => ScreenData::Stations(std::mem::take(&mut self.stations)),
+            View::History => ScreenData::History(std::mem::take(&mut self.history)),
+            View::Stats => self.stats.take().map_or(ScreenData::None, |stats| ScreenData::Stats(Box::new(stats))),
+            View::Favorites | View::FavoriteCountries | View::Map => ScreenData::None,
+        };
+        Screen {
+            view: self.current_view,
+            label: std::mem::take(&mut self.view_label),
+            list_state: std::mem::take(&mut self.list.state),
+            filter: std::mem::take(&mut self.filter.query),
+            data,
+        }
+    }
+
+    fn restore_screen(&mut self, screen: Screen) {
+        self.current_view = screen.view;
+        self.view_label = screen.label;
+        self.list.state = screen.list_state;
+        self.filter.query = screen.filter;
+        match screen.data {
+            ScreenData::Results(hits) => self.search_results = hits,
+            ScreenData::Stations(stations) => self.stations = stations,
+            ScreenData::History(history) => self.history = history,
+            ScreenData::Stats(stats) => self.stats = Some(*stats),
+            ScreenData::None => {}
+        }
+        // Favorites may have changed while away.
+        let len = self.list_len();
+        if self.list.state.selected().is_some_and(|selected| selected >= len) {
+            self.list.state.select(len.checked_sub(1));
+        }
+    }
+
+    fn input_mut(&mut self) -> &mut String {
+        if self.current_view == View::Search {
+            &mut self.search.input
+        } else {
+            &mut self.filter.query
+        }
+    }
+
+    fn clear_filter(&mut self) {
+        self.filter.query.clear();
+        self.list.state.select(Some(0));
+    }
+
+    /// The current view's rows, in the order of the underlying list.
+    fn rows(&self) -> Vec<Row> {
+        match self.current_view {
+            View::Stats | View::Map => Vec::new(),
+            View::Search | View::Results => self.search_results
+                .iter()
+                .map(|hit| {
+                    let icon = match hit.source.result_type.as_str() {
+                        "country" => {
+                            let is_favorite = hit.source.url.as_ref()
+                                .and_then(|url| url.split('/').next_back())
+                                .map(|id| self.is_country_favorite(id))
+                                .unwrap_or(false);
+                            self.icon(is_favorite, self.theme.icons.country)
+                        },
+                        "channel" => {
+                            let is_favorite = hit.source.page.as_ref()
+                                .and_then(|p| p.url.split('/').next_back())
+                                .map(|id| self.is_station_favorite(id))
+                                .unwrap_or(false);
+                            self.icon(is_favorite, self.theme.icons.station)
+                        },
+                        _ => self.icon(false, self.theme.icons.unknown),
+                    };
+                    Row::new(icon, hit.source.title.clone(), "")
+                })
+                .collect(),
+            View::Stations => self.stations
+                .iter()
+                .map(|station| {
+                    let is_favorite = station.page.url.split('/').next_back()
+                        .map(|id| self.is_station_favorite(id))
+                        .unwrap_or(false);
+                    Row::new(self.icon(is_favorite, self.theme.icons.station), station.title.clone(), "")
+                })
+                .collect(),
+            View::Favorites => {
+                // Favorite countries first, then stations
+                let countries = self.favorites.countries.iter().map(|country| self.favorite_country_row(country));
+                let stations = self.favorites.stations.iter().map(|station| {
+                    // Renamed stations keep their own title next to the name.
+                    let title = station.name.as_ref().map(|_| station.title.as_str());
+                    let country = station.country.as_deref().unwrap_or("Unknown");
+                    let details: Vec<&str> = title.into_iter().chain([country]).collect();
+                    let suffix = favorite_suffix(&details, station.note.as_deref(), self.theme.icons.dot);
+                    Row::new(self.icon(true, self.theme.icons.station), station.display_name().to_string(), suffix)
+                });
+                countries.chain(stations).collect()
             }
-                    KeyCode::Char(' ') => {
-                        if self.current_view == View::Search {
-                            self.search_input.push(' ');
-                        } else {
-                            if self.player.is_paused() {
-                                self.player.resume();
-                                self.status_message = "▶️ Resumed playback".to_string();
-                            } else {
-                                self.player.pause();
-                                self.status_message = "⏸️ Paused playback".to_string();
-                            }
+            View::FavoriteCountries => self.favorites.countries
+                .iter()
+                .map(|country| self.favorite_country_row(country))
+                .collect(),
+            View::History => self.history
+                .iter()
+                .map(|entry| {
+                    let icon = self.icon(self.is_station_favorite(&entry.station.id), self.theme.icons.station);
+                    let country = entry.station.country.as_ref()
+                        .map(|c| format!(" ({})", c))
+                        .unwrap_or_default();
+                    let last_track = entry.tracks.last()
+                        .map(|t| format!(" {} {} {}", self.theme.icons.dot, self.theme.icons.track, t.title))
+                        .unwrap_or_default();
+                    Row::new(
+                        format!("{}  {}", entry.started.with_timezone(&Local).format("%a %d %b %H:%M"), icon),
+                        entry.station.title.clone(),
+                        format!("{} {} {}{}", country, self.theme.icons.dot, format_duration(entry.duration()), last_track),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// A list icon with its trailing space, marked when it's a favorite.
+    fn icon(&self, favorite: bool, icon: &str) -> String {
+        let mark = if favorite { self.theme.icons.favorite } else { "" };
+        format!("{}{} ", mark, icon)
+    }
+
+    /// Rows that pass the filter, in display order.
+    fn visible(&self) -> Vec<Visible> {
+        self.filter.apply(&self.rows())
+    }
+
+    /// Index into the underlying list of the selected row.
+    fn selected_index(&self) -> Option<usize> {
+        let selected = self.list.state.selected()?;
+        self.visible().get(selected).map(|visible| visible.index)
+    }
+
+    fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::Player(event) => self.handle_player_event(event),
+            Message::Status(result) => {
+                self.polling = false;
+                // A failed poll is retried on the next tick.
+                if let Ok(status) = result {
+                    self.now_playing.sync(status);
+                }
+            }
+            Message::Controlled(status) => self.status_message = status,
+            Message::SearchResults { request, query, live, result } => {
+                if !self.finish_loading(request) {
+                    return;
+                }
+                match result {
+                    // Typing may have moved on to another view by now.
+                    Ok(results) if live => {
+                        if self.current_view == View::Search && self.search.input.trim() == query {
+                            self.search_results = results.hits.hits;
+                            self.search.live_query = Some(query);
+                            self.list.state.select(Some(0));
                         }
                     }
-                    KeyCode::Char(c) => {
-                        if self.current_view == View::Search {
-                            self.search_input.push(c);
-                        } else {
-                            match c {
-                                'a' => self.add_to_favorites(),
-                                'x' => {
-                                    self.player.stop();
-                                    self.current_station = None;
-                                    self.status_message = "⏹️ Stopped playback".to_string();
-                                }
-                                _ => {}
-                            }
-                        }
+                    Ok(results) => {
+                        self.navigate(View::Results, &query);
+                        self.search_results = results.hits.hits;
+                        self.status_message = controls_hint(&self.keymap);
                     }
-                    KeyCode::Backspace => {
-                        if self.current_view == View::Search {
-                            self.search_input.pop();
+                    Err(e) => {
+                        self.status_message = format!("Search failed: {}", e);
+                    }
+                }
+            }
+            Message::CountryLookup { request, name, result } => {
+                if !self.finish_loading(request) {
+                    return;
+                }
+                match result {
+                    Ok(results) => {
+                        let countries: Vec<&Hit> = results.hits.hits
+                            .iter()
+                            .filter(|hit| hit.source.result_type == "country")
+                            .collect();
+                        let best = countries
+                            .iter()
+                            .find(|hit| hit.source.title.eq_ignore_ascii_case(&name))
+                            .or(countries.first());
+                        let country = best.and_then(|hit| {
+                            let id = hit.source.url.as_ref()?.split('/').next_back()?;
+                            Some((id.to_string(), hit.source.title.clone()))
+                        });
+                        match country {
+                            Some((country_id, title)) => self.load_country_stations(&country_id, &title),
+                            None => self.status_message = format!("No country found for '{}'", name),
                         }
                     }
-            KeyCode::Esc => {
-                match self.current_view {
-                    View::Stations => self.current_view = View::Results,
-                    View::Results => self.current_view = View::Search,
-                    View::Favorites | View::FavoriteCountries => self.current_view = View::Search,
-                    _ => {}
+                    Err(e) => {
+                        self.status_message = format!("Search failed: {}", e);
+                    }
                 }
             }
-                    _ => {}
+            Message::Places { request, result } => {
+                if !self.finish_loading(request) {
+                    return;
                 }
+                match result {
+                    Ok(places) => {
+                        self.map.places = places.data.list;
+                        self.navigate(View::Map, "Map");
+                        self.status_message = format!("Loaded {} places", self.map.places.len());
+                        self.locate_stations();
+                        self.load_nearby_stations();
+                    }
+                    Err(e) => {
+                        self.status_message = format!("Failed to load the map: {}", e);
+                    }
+                }
+            }
+            Message::StationsPlaced(placed) => self.map.station_places.extend(placed),
+            Message::PlaceStations { place_id, result } => match result {
+                Ok(page) => self.map.loaded(&place_id, Some(page_stations(page))),
+                Err(e) => {
+                    self.map.loaded(&place_id, None);
+                    self.status_message = format!("Failed to load stations near the cursor: {}", e);
+                }
+            },
+            Message::CountryStations { request, country_id, title, result } => {
+                if !self.finish_loading(request) {
+                    return;
+                }
+                match result {
+                    Ok(country_page) => {
+                        self.navigate(View::Stations, &title);
+                        self.stations = page_stations(country_page);
+                        self.status_message = format!("Loaded {} stations", self.stations.len());
+                    }
+                    Err(e) => {
+                        self.status_message = format!("Failed to load stations for {}: {}", country_id, e);
+                    }
+                }
+            }
+        }
+    }
+
+    fn handle_player_event(&mut self, event: PlayerEvent) {
+        self.now_playing.apply(&event);
+        match event {
+            PlayerEvent::DeadAir { station, reason, skipping } => {
+                self.status_message = format!(
+                    "{} {} seems dead ({}){}",
+                    self.theme.icons.warning,
+                    station.title,
+                    reason.label(),
+                    if skipping { ", switching to the next favorite" } else { "" }
+                );
+            }
+            PlayerEvent::FellBack { station, stream } => {
+                self.status_message = format!("{} {} is playing from fallback {}", self.theme.icons.fallback, station.title, stream);
+            }
+            PlayerEvent::Unavailable { station, reason } => {
+                self.status_message = format!("{} {} could not be played: {}", self.theme.icons.removed, station.title, reason);
+            }
+            PlayerEvent::Playing { .. } => self.locate_stations(),
+            _ => {}
+        }
+    }
+
+    /// Runs `request` in the background, replacing (and cancelling) any
+    /// request still in flight. `request` gets the ID its message must carry.
+    fn start_loading<F>(&mut self, label: String, request: impl FnOnce(u64) -> F)
+    where
+        F: Future<Output = Message> + Send + 'static,
+    {
+        if let Some(previous) = self.loading.take() {
+            previous.task.abort();
+        }
+        self.next_request += 1;
+        let id = self.next_request;
+        let work = request(id);
+        let sender = self.sender.clone();
+        let task = tokio::spawn(async move {
+            let _ = sender.send(work.await);
+        });
+        self.loading = Some(Loading {
+            request: id,
+            label,
+            started: Instant::now(),
+            task,
+        });
+    }
+
+    /// Whether `request` is the one in flight, which it then no longer is.
+    /// Results of cancelled or replaced requests are dropped.
+    fn finish_loading(&mut self, request: u64) -> bool {
+        if self.loading.as_ref().is_some_and(|loading| loading.request == request) {
+            self.loading = None;
+            return true;
+        }
+        false
+    }
+
+    /// Cancels the request in flight. Returns whether there was one.
+    fn cancel_loading(&mut self) -> bool {
+        let Some(loading) = self.loading.take() else { return false };
+        loading.task.abort();
+        self.status_message = format!("Cancelled: {}", loading.label);
+        true
+    }
+
+    /// Searches what's typed and shows the results, which are also what the
+    /// live results under the input are when they're for the same query.
+    fn search(&mut self) {
+        let query = self.search.input.trim().to_string();
+        self.search.cancel_due();
+        if let Err(e) = self.search.history.add(&query) {
+            self.status_message = format!("Couldn't save search history: {}", e);
+        }
+        if self.search.live_query.as_deref() == Some(query.as_str()) {
+            let hits = self.search_results.clone();
+            self.navigate(View::Results, &query);
+            self.search_results = hits;
+            return;
+        }
+        let client = self.client.clone();
+        self.start_loading(format!("Searching for '{}'", query), |request| async move {
+            let result = client.search(&query).await;
+            Message::SearchResults { request, query, live: false, result }
+        });
+    }
+
+    /// Searches while typing; a newer query or any other request cancels it.
+    fn live_search(&mut self, query: String) {
+        let client = self.client.clone();
+        self.start_loading(format!("Searching for '{}'", query), |request| async move {
+            let result = client.search(&query).await;
+            Message::SearchResults { request, query, live: true, result }
+        });
+    }
+
+    /// After typing into the search box or the filter.
+    fn input_edited(&mut self, filtering: bool) {
+        if filtering {
+            self.list.state.select(Some(0));
+        } else {
+            self.search.edited();
+            self.drop_results_if_empty();
+        }
+    }
+
+    /// Nothing's typed, so nothing's searched.
+    fn drop_results_if_empty(&mut self) {
+        if self.search.input.trim().is_empty() {
+            self.search_results.clear();
+        }
+    }
+
+    /// Favorite countries, for completing searches.
+    fn favorite_country_titles(&self) -> Vec<String> {
+        self.favorites.countries.iter().map(|c| c.title.clone()).collect()
+    }
+
+    fn load_country_stations(&mut self, country_id: &str, title: &str) {
+        let client = self.client.clone();
+        let country_id = country_id.to_string();
+        let title = title.to_string();
+        self.start_loading(format!("Loading stations for {}", title), |request| async move {
+            let result = client.get_country_stations(&country_id).await;
+            Message::CountryStations { request, country_id, title, result }
+        });
+    }
+
+    /// Loads the stations of places near the map cursor in the background,
+    /// each place once. They don't replace the request in flight, if any.
+    fn load_nearby_stations(&mut self) {
+        for place_id in self.map.places_to_load() {
+            let client = self.client.clone();
+            let sender = self.sender.clone();
+            tokio::spawn(async move {
+                let result = client.get_place_stations(&place_id).await;
+                let _ = sender.send(Message::PlaceStations { place_id, result });
+            });
+        }
+    }
+
+    /// Opens the map, loading its places the first time.
+    fn open_map(&mut self) {
+        if !self.map.places.is_empty() {
+            self.navigate(View::Map, "Map");
+            self.locate_stations();
+            self.load_nearby_stations();
+            return;
+        }
+        let client = self.client.clone();
+        self.start_loading("Loading the map".to_string(), |request| async move {
+            let result = client.get_places().await;
+            Message::Places { request, result }
+        });
+    }
+
+    /// Looks up in the background where favorite and playing stations not on
+    /// the map yet broadcast from, so they can be marked on it.
+    fn locate_stations(&mut self) {
+        if self.map.places.is_empty() {
+            return;
+        }
+        let mut unplaced: Vec<String> = Vec::new();
+        let playing = self.now_playing.station.iter().map(|station| &station.id);
+        for id in self.favorites.stations.iter().map(|station| &station.id).chain(playing) {
+            if !self.map.station_places.contains_key(id) && !unplaced.contains(id) {
+                unplaced.push(id.clone());
             }
         }
+        if unplaced.is_empty() {
+            return;
+        }
+        let client = self.client.clone();
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let channels = join_all(unplaced.iter().map(|id| client.get_channel(id))).await;
+            let placed = unplaced
+                .into_iter()
+                .zip(channels)
+                .filter_map(|(id, channel)| Some((id, channel.ok()?.data.place.id)))
+                .collect();
+            let _ = sender.send(Message::StationsPlaced(placed));
+        });
     }
 
-    async fn search(&mut self) -> Result<()> {
-        self.status_message = format!("Searching for '{}'...", self.search_input);
-        match self.client.search(&self.search_input).await {
-            Ok(results) => {
-                self.search_results = results.hits.hits;
-                self.current_view = View::Results;
-                self.list_state.select(Some(0));
-                self.status_message = "Controls: Ctrl+s=search, Ctrl+f=favorites, Ctrl+c=countries, 'a'=favorite, SPACE=pause/play, 'x'=stop, 'q'=quit".to_string();
+    /// Opens a country's stations by name: a favorite if one matches, or
+    /// else the best country hit from a search.
+    fn open_country(&mut self, name: &str) {
+        if let Some(country) = self.favorites.countries.iter().find(|c| c.title.eq_ignore_ascii_case(name) || c.display_name().eq_ignore_ascii_case(name)) {
+            let (country_id, title) = (country.id.clone(), country.title.clone());
+            self.load_country_stations(&country_id, &title);
+            return;
+        }
+        let client = self.client.clone();
+        let name = name.to_string();
+        self.start_loading(format!("Looking up country '{}'", name), |request| async move {
+            let result = client.search(&name).await;
+            Message::CountryLookup { request, name, result }
+        });
+    }
+
+    fn load_history(&mut self) {
+        match History::load() {
+            Ok(history) => {
+                self.navigate(View::History, "Recent");
+                self.history = history.since(None).take(RECENT_LIMIT).cloned().collect();
+                self.status_message = format!("{} recently played sessions (Enter to replay)", self.history.len());
             }
             Err(e) => {
-                self.status_message = format!("Search failed: {}", e);
+                self.status_message = format!("Failed to load history: {}", e);
             }
         }
-        Ok(())
     }
 
-    async fn load_country_stations(&mut self, country_id: &str) -> Result<()> {
-        self.status_message = format!("Loading stations for ID: {}...", country_id);
-        match self.client.get_country_stations(country_id).await {
-            Ok(country_page) => {
-                self.stations.clear();
-                for content_item in country_page.data.content {
-                    if let Some(station_items) = content_item.items {
-                        for station_item in station_items {
-                            let station = Station {
-                                title: station_item.page.title.clone(),
-                                page: Page {
-                                    url: station_item.page.url.clone(),
-                                },
-                            };
-                            self.stations.push(station);
-                        }
-                    }
-                }
-                self.current_view = View::Stations;
-                self.list_state.select(Some(0));
-                self.status_message = format!("Loaded {} stations", self.stations.len());
+    fn load_stats(&mut self) {
+        match Stats::load(self.stats_period) {
+            Ok(stats) => {
+                self.navigate(View::Stats, "Stats");
+                self.stats = Some(stats);
+                self.status_message = format!("Listening stats for {}", self.stats_period.label());
             }
             Err(e) => {
-                self.status_message = format!("Failed to load stations for {}: {}", country_id, e);
+                self.status_message = format!("Failed to load history: {}", e);
             }
         }
-        Ok(())
     }
 
-    fn play_station(&mut self, station_id: &str, title: &str) -> Result<()> {
-        let stream_url = self.client.get_stream_url(station_id);
-        self.player.play_url(stream_url)?;
-        self.current_station = Some(title.to_string());
-        self.status_message = format!("♪ Playing: {} (Press 'a' to favorite)", title);
+    fn play_station(&mut self, station_id: &str, title: &str, country: Option<String>) -> Result<()> {
+        let station = StationInfo {
+            id: station_id.to_string(),
+            title: title.to_string(),
+            country,
+        };
+        let icon = self.theme.icons.track;
+        let title = title.to_string();
+        let hint = key_hint(&self.keymap, Action::ToggleFavorite, "favorite");
+        self.control(|playback| async move {
+            match playback.play(station).await {
+                Ok(()) => format!("{} Playing: {} ({})", icon, title, hint),
+                Err(e) => format!("Failed to play {}: {}", title, e),
+            }
+        });
         Ok(())
     }
 
     fn add_to_favorites(&mut self) {
         match self.current_view {
-            View::Results => {
-                if let Some(selected) = self.list_state.selected() {
+            View::Search | View::Results => {
+                if let Some(selected) = self.selected_index() {
                     if let Some(hit) = self.search_results.get(selected) {
                         if hit.source.result_type == "country" {
                             if let Some(url) = &hit.source.url {
-                                if let Some(country_id) = url.split('/').last() {
+                                if let Some(country_id) = url.split('/').next_back() {
                                     let country_title = hit.source.title.clone();
                                     if self.is_country_favorite(country_id) {
-                                        if self.favorites.remove_country(country_id).is_ok() {
-                                            self.status_message = format!("❌ Removed {} from favorites", country_title);
+                                        if let Ok(Some(removed)) = self.favorites.remove_country(country_id) {
+                                            self.removed = Some(removed);
+                                            self.status_message = format!("{} Removed {} from favorites", self.theme.icons.removed, country_title);
                                             self.favorites = Favorites::load().unwrap_or_default();
                                         }
                                     } else {
                                         let fav_country = FavoriteCountry {
                                             id: country_id.to_string(),
                                             title: country_title.clone(),
+                                            name: None,
+                                            note: None,
                                         };
                                         if self.favorites.add_country(fav_country).is_ok() {
-                                            self.status_message = format!("⭐ Added {} to favorites", country_title);
+                                            self.status_message = format!("{} Added {} to favorites", self.theme.icons.favorite, country_title);
                                             self.favorites = Favorites::load().unwrap_or_default();
                                         }
                                     }
@@ -334,10 +1529,11 @@ impl App {
                             }
                         } else if hit.source.result_type == "channel" {
                             if let Some(page) = &hit.source.page {
-                                if let Some(station_id) = page.url.split('/').last() {
+                                if let Some(station_id) = page.url.split('/').next_back() {
                                     if self.is_station_favorite(station_id) {
-                                        if self.favorites.remove_station(station_id).is_ok() {
-                                            self.status_message = format!("❌ Removed {} from favorites", hit.source.title);
+                                        if let Ok(Some(removed)) = self.favorites.remove_station(station_id) {
+                                            self.removed = Some(removed);
+                                            self.status_message = format!("{} Removed {} from favorites", self.theme.icons.removed, hit.source.title);
                                             self.favorites = Favorites::load().unwrap_or_default();
                                         }
                                     } else {
@@ -345,9 +1541,12 @@ impl App {
                                             id: station_id.to_string(),
                                             title: hit.source.title.clone(),
                                             country: hit.source.country.as_ref().map(|c| c.title.clone()),
+                                            name: None,
+                                            note: None,
+                                            fallbacks: Vec::new(),
                                         };
                                         if self.favorites.add_station(fav_station).is_ok() {
-                                            self.status_message = format!("⭐ Added {} to favorites", hit.source.title);
+                                            self.status_message = format!("{} Added {} to favorites", self.theme.icons.favorite, hit.source.title);
                                             self.favorites = Favorites::load().unwrap_or_default();
                                         }
                                     }
@@ -358,12 +1557,13 @@ impl App {
                 }
             }
             View::Stations => {
-                if let Some(selected) = self.list_state.selected() {
+                if let Some(selected) = self.selected_index() {
                     if let Some(station) = self.stations.get(selected) {
-                        if let Some(station_id) = station.page.url.split('/').last() {
+                        if let Some(station_id) = station.page.url.split('/').next_back() {
                             if self.is_station_favorite(station_id) {
-                                if self.favorites.remove_station(station_id).is_ok() {
-                                    self.status_message = format!("❌ Removed {} from favorites", station.title);
+                                if let Ok(Some(removed)) = self.favorites.remove_station(station_id) {
+                                    self.removed = Some(removed);
+                                    self.status_message = format!("{} Removed {} from favorites", self.theme.icons.removed, station.title);
                                     self.favorites = Favorites::load().unwrap_or_default();
                                 }
                             } else {
@@ -371,9 +1571,12 @@ impl App {
                                     id: station_id.to_string(),
                                     title: station.title.clone(),
                                     country: None,
+                                    name: None,
+                                    note: None,
+                                    fallbacks: Vec::new(),
                                 };
                                 if self.favorites.add_station(fav_station).is_ok() {
-                                    self.status_message = format!("⭐ Added {} to favorites", station.title);
+                                    self.status_message = format!("{} Added {} to favorites", self.theme.icons.favorite, station.title);
                                     self.favorites = Favorites::load().unwrap_or_default();
                                 }
                             }
@@ -381,51 +1584,232 @@ impl App {
                     }
                 }
             }
-            View::Favorites => {
-                if let Some(selected) = self.list_state.selected() {
-                    let countries_count = self.favorites.countries.len();
-                    if selected < countries_count {
-                        // Selected a country - remove it
-                        if let Some(country) = self.favorites.countries.get(selected) {
-                            let id = country.id.clone();
-                            let title = country.title.clone();
-                            if self.favorites.remove_country(&id).is_ok() {
-                                self.status_message = format!("❌ Removed {} from favorites", title);
+            // Countries come first in the favorites list, so the indexes match.
+            View::Favorites | View::FavoriteCountries => {
+                if let Some(selected) = self.selected_index() {
+                    self.ask_removal(selected);
+                }
+            }
+            View::History => {
+                if let Some(selected) = self.selected_index() {
+                    if let Some(entry) = self.history.get(selected) {
+                        let station = entry.station.clone();
+                        if self.is_station_favorite(&station.id) {
+                            if let Ok(Some(removed)) = self.favorites.remove_station(&station.id) {
+                                self.removed = Some(removed);
+                                self.status_message = format!("{} Removed {} from favorites", self.theme.icons.removed, station.title);
                                 self.favorites = Favorites::load().unwrap_or_default();
                             }
-                        }
-                    } else {
-                        // Selected a station - remove it
-                        let station_index = selected - countries_count;
-                        if let Some(station) = self.favorites.stations.get(station_index) {
-                            let id = station.id.clone();
-                            let title = station.title.clone();
-                            if self.favorites.remove_station(&id).is_ok() {
-                                self.status_message = format!("❌ Removed {} from favorites", title);
+                        } else {
+                            let fav_station = FavoriteStation {
+                                id: station.id,
+                                title: station.title.clone(),
+                                country: station.country,
+                                name: None,
+                                note: None,
+                                fallbacks: Vec::new(),
+                            };
+                            if self.favorites.add_station(fav_station).is_ok() {
+                                self.status_message = format!("{} Added {} to favorites", self.theme.icons.favorite, station.title);
                                 self.favorites = Favorites::load().unwrap_or_default();
                             }
                         }
                     }
                 }
             }
-            View::FavoriteCountries => {
-                if let Some(selected) = self.list_state.selected() {
-                    if let Some(country) = self.favorites.countries.get(selected) {
-                        let id = country.id.clone();
-                        let title = country.title.clone();
-                        if self.favorites.remove_country(&id).is_ok() {
-                            self.status_message = format!("❌ Removed {} from favorites", title);
-                            self.favorites = Favorites::load().unwrap_or_default();
-                        }
-                    }
-                }
-            }
             _ => {
                 self.status_message = "Cannot toggle favorites from this view".to_string();
             }
         }
     }
 
+    /// Opens the country or plays the station at `index` of the favorites
+    /// list, countries first as in the favorites view and the sidebar.
+    fn select_favorite(&mut self, index: usize) -> Result<()> {
+        let countries_count = self.favorites.countries.len();
+        if index < countries_count {
+            // Selected a country - load its stations
+            let country = &self.favorites.countries[index];
+            let (country_id, title) = (country.id.clone(), country.title.clone());
+            self.load_country_stations(&country_id, &title);
+        } else if let Some(station) = self.favorites.stations.get(index - countries_count) {
+            // Selected a station - play it
+            let station_id = station.id.clone();
+            let station_title = station.title.clone();
+            let country = station.country.clone();
+            self.play_station(&station_id, &station_title, country)?;
+        }
+        Ok(())
+    }
+
+    /// The favorite at `index` of the favorites list, countries first.
+    fn favorite_entry(&self, index: usize) -> Option<FavoriteEntry> {
+        let countries = &self.favorites.countries;
+        match countries.get(index) {
+            Some(country) => Some(FavoriteEntry::Country(country.id.clone())),
+            None => self
+                .favorites
+                .stations
+                .get(index - countries.len())
+                .map(|station| FavoriteEntry::Station(station.id.clone())),
+        }
+    }
+
+    fn favorite_note(&self, index: usize) -> Option<String> {
+        let countries = &self.favorites.countries;
+        match countries.get(index) {
+            Some(country) => country.note.clone(),
+            None => self.favorites.stations.get(index - countries.len())?.note.clone(),
+        }
+    }
+
+    /// Index in the favorites list of the favorite selected in the sidebar
+    /// or a favorites view.
+    fn selected_favorite(&self) -> Option<usize> {
+        match (self.focus, self.current_view) {
+            (Pane::Sidebar, _) => self.sidebar.state.selected(),
+            (_, View::Favorites | View::FavoriteCountries) => self.selected_index(),
+            _ => None,
+        }
+    }
+
+    /// Asks before removing the favorite at `index` of the favorites list;
+    /// the next key removes it if it's 'y'.
+    fn ask_removal(&mut self, index: usize) {
+        let (Some(entry), Some(name)) = (self.favorite_entry(index), self.sidebar_labels().get(index).cloned()) else {
+            return;
+        };
+        self.status_message = format!("Remove {} from favorites? (y/n)", name);
+        self.pending_removal = Some((entry, name));
+    }
+
+    fn remove_favorite(&mut self, entry: FavoriteEntry) {
+        let result = match &entry {
+            FavoriteEntry::Country(id) => self.favorites.remove_country(id),
+            FavoriteEntry::Station(id) => self.favorites.remove_station(id),
+        };
+        match result {
+            Ok(Some(removed)) => {
+                let undo = self
+                    .keymap
+                    .keys(Action::Undo)
+                    .first()
+                    .map(|key| format!(" ({} to undo)", key))
+                    .unwrap_or_default();
+                self.status_message = format!(
+                    "{} Removed {} from favorites{}",
+                    self.theme.icons.removed,
+                    removed.display_name(),
+                    undo
+                );
+                self.removed = Some(removed);
+                self.favorites = Favorites::load().unwrap_or_default();
+            }
+            Ok(None) => {}
+            Err(e) => self.status_message = format!("Couldn't save favorites: {}", e),
+        }
+    }
+
+    /// Puts the last favorite removed back where it was.
+    fn undo_removal(&mut self) {
+        let Some(removed) = self.removed.take() else {
+            self.status_message = "Nothing to undo".to_string();
+            return;
+        };
+        let name = removed.display_name().to_string();
+        self.status_message = match self.favorites.restore(removed) {
+            Ok(()) => format!("{} Restored {} to favorites", self.theme.icons.favorite, name),
+            Err(e) => format!("Couldn't save favorites: {}", e),
+        };
+    }
+
+    /// Moves the selected favorite up or down among the countries or the
+    /// stations, taking the selection along.
+    fn move_favorite(&mut self, up: bool) {
+        let Some(index) = self.selected_favorite() else {
+            self.status_message = "Select a favorite to move it".to_string();
+            return;
+        };
+        if self.focus != Pane::Sidebar && self.filter.is_active() {
+            self.status_message = "Clear the filter to reorder favorites".to_string();
+            return;
+        }
+        let result = match self.favorite_entry(index) {
+            Some(FavoriteEntry::Country(id)) => self.favorites.move_country(&id, up),
+            Some(FavoriteEntry::Station(id)) => self.favorites.move_station(&id, up),
+            None => return,
+        };
+        match result {
+            Ok(true) => {
+                let index = if up { index - 1 } else { index + 1 };
+                let list = if self.focus == Pane::Sidebar { &mut self.sidebar } else { &mut self.list };
+                list.state.select(Some(index));
+            }
+            Ok(false) => {}
+            Err(e) => self.status_message = format!("Couldn't save favorites: {}", e),
+        }
+    }
+
+    /// Opens the palette on `:rename` or `:note` with the selected
+    /// favorite's name or note, to edit.
+    fn edit_favorite(&mut self, command: &str) {
+        let Some(index) = self.selected_favorite() else {
+            self.status_message = format!("Select a favorite to {} it", command);
+            return;
+        };
+        let current = if command == "rename" {
+            self.sidebar_labels().get(index).cloned()
+        } else {
+            self.favorite_note(index)
+        };
+        self.palette = Some(Palette {
+            input: format!("{} {}", command, current.unwrap_or_default()),
+            ..Palette::default()
+        });
+    }
+
+    fn rename_favorite(&mut self, name: Option<String>) {
+        let Some(index) = self.selected_favorite() else {
+            self.status_message = "Select a favorite to rename it".to_string();
+            return;
+        };
+        let result = match self.favorite_entry(index) {
+            Some(FavoriteEntry::Country(id)) => self.favorites.rename_country(&id, name.clone()),
+            Some(FavoriteEntry::Station(id)) => self.favorites.rename_station(&id, name.clone()),
+            None => return,
+        };
+        let icon = self.theme.icons.saved;
+        self.status_message = match (result, name) {
+            (Ok(_), Some(name)) => format!("{} Renamed to {}", icon, name),
+            (Ok(_), None) => format!("{} Back to its original title", icon),
+            (Err(e), _) => format!("Couldn't save favorites: {}", e),
+        };
+    }
+
+    fn annotate_favorite(&mut self, note: Option<String>) {
+        let Some(index) = self.selected_favorite() else {
+            self.status_message = "Select a favorite to add a note to it".to_string();
+            return;
+        };
+        let result = match self.favorite_entry(index) {
+            Some(FavoriteEntry::Country(id)) => self.favorites.set_country_note(&id, note.clone()),
+            Some(FavoriteEntry::Station(id)) => self.favorites.set_station_note(&id, note.clone()),
+            None => return,
+        };
+        let icon = self.theme.icons.saved;
+        self.status_message = match (result, note) {
+            (Ok(_), Some(_)) => format!("{} Note saved", icon),
+            (Ok(_), None) => format!("{} Note cleared", icon),
+            (Err(e), _) => format!("Couldn't save favorites: {}", e),
+        };
+    }
+
+    fn favorite_country_row(&self, country: &FavoriteCountry) -> Row {
+        let title: Vec<&str> = country.name.as_ref().map(|_| country.title.as_str()).into_iter().collect();
+        let suffix = favorite_suffix(&title, country.note.as_deref(), self.theme.icons.dot);
+        Row::new(self.icon(true, self.theme.icons.country), country.display_name().to_string(), suffix)
+    }
+
     fn is_station_favorite(&self, station_id: &str) -> bool {
         self.favorites.stations.iter().any(|s| s.id == station_id)
     }
@@ -435,108 +1819,403 @@ impl App {
     }
 
     fn ui(&mut self, f: &mut Frame) {
+        let now_playing_height = self.now_playing.height();
         let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Min(0), Constraint::Length(3)])
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(now_playing_height),
+                Constraint::Length(3),
+            ])
             .split(f.size());
 
-        // Header
-        let header = Paragraph::new("🎵 Termadio - Terminal Radio")
-            .style(Style::default().fg(Color::Cyan))
-            .block(Block::default().borders(Borders::ALL));
+        self.clickable.clear();
+        self.list_area = None;
+        self.sidebar_area = None;
+        self.map_areas = None;
+        self.panel_area = chunks[2];
+
+        // Favorites sidebar left of the main pane, when the terminal is wide enough
+        self.split = self.layout.sidebar && f.size().width >= self.layout.min_width;
+        let panes = self.panes();
+        if !panes.contains(&self.focus) {
+            self.focus = Pane::Main;
+        }
+        // Focus is only marked when there's more than one pane to move it to.
+        let focused = |pane| panes.len() > 1 && self.focus == pane;
+        let (main_focused, sidebar_focused, panel_focused) =
+            (focused(Pane::Main), focused(Pane::Sidebar), focused(Pane::NowPlaying));
+        let main_area = if self.split {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(self.layout.sidebar_width), Constraint::Min(0)])
+                .split(chunks[1]);
+            self.render_sidebar(f, columns[0], sidebar_focused);
+            columns[1]
+        } else {
+            chunks[1]
+        };
+
+        // Header, with a tab per top-level view
+        let theme = self.theme;
+        let header = theme
+            .block()
+            .title(format!("{}Termadio - Terminal Radio", theme.icons.logo))
+            .style(theme.accent);
+        let tabs_area = header.inner(chunks[0]);
         f.render_widget(header, chunks[0]);
+        let current_tab = match self.current_view {
+            View::Results | View::Stations => View::Search,
+            _ => self.current_view,
+        };
+        let mut tabs = Vec::new();
+        let mut x = tabs_area.x;
+        for (label, action, view) in TABS {
+            let text = format!(" {} ", label);
+            let width = (text.len() as u16).min(tabs_area.right().saturating_sub(x));
+            self.clickable.push((Rect::new(x, tabs_area.y, width, 1), action));
+            x += text.len() as u16 + 1;
+            let style = if view == current_tab { theme.active_tab } else { theme.accent };
+            tabs.push(Span::styled(text, style));
+            tabs.push(Span::styled(theme.icons.divider, theme.muted));
+        }
+        tabs.pop();
+        f.render_widget(Paragraph::new(Line::from(tabs)), tabs_area);
+
+        // Breadcrumbs right of the tabs, dropping the oldest when they don't fit
+        let trail_area = Rect::new(x, tabs_area.y, tabs_area.right().saturating_sub(x + 1), 1);
+        let trail = self.breadcrumbs(trail_area.width as usize);
+        f.render_widget(
+            Paragraph::new(trail).style(theme.muted).alignment(Alignment::Right),
+            trail_area,
+        );
 
         // Main content
         match self.current_view {
             View::Search => {
-                let input = Paragraph::new(self.search_input.as_str())
-                    .style(Style::default().fg(Color::Yellow))
-                    .block(Block::default().borders(Borders::ALL).title("Search"));
-                f.render_widget(input, chunks[1]);
-            }
-            View::Results => {
-                let items: Vec<ListItem> = self.search_results
-                    .iter()
-                    .map(|hit| {
-                        let (icon, _is_fav) = match hit.source.result_type.as_str() {
-                            "country" => {
-                                let is_favorite = hit.source.url.as_ref()
-                                    .and_then(|url| url.split('/').last())
-                                    .map(|id| self.is_country_favorite(id))
-                                    .unwrap_or(false);
-                                (if is_favorite { "⭐🌍" } else { "🌍" }, is_favorite)
-                            },
-                            "channel" => {
-                                let is_favorite = hit.source.page.as_ref()
-                                    .and_then(|p| p.url.split('/').last())
-                                    .map(|id| self.is_station_favorite(id))
-                                    .unwrap_or(false);
-                                (if is_favorite { "⭐📻" } else { "📻" }, is_favorite)
-                            },
-                            _ => ("❓", false),
-                        };
-                        ListItem::new(format!("{} {}", icon, hit.source.title))
-                    })
-                    .collect();
+                let parts = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(0)])
+                    .split(main_area);
+                // The first completion shows after the input; Tab takes it.
+                let completion = self
+                    .search
+                    .completions(&self.favorite_country_titles())
+                    .into_iter()
+                    .next()
+                    .filter(|_| self.mode == Mode::Insert)
+                    .map(|completion| completion.chars().skip(self.search.input.chars().count()).collect::<String>())
+                    .unwrap_or_default();
+                let input = Paragraph::new(Line::from(vec![
+                    Span::styled(self.search.input.as_str(), theme.highlight),
+                    Span::styled(completion, theme.muted),
+                ]))
+                .block(theme.pane(main_focused).title("Search"));
+                f.render_widget(input, parts[0]);
 
-                let list = List::new(items)
-                    .block(Block::default().borders(Borders::ALL).title("Search Results"))
-                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
-                f.render_stateful_widget(list, chunks[1], &mut self.list_state);
+                let rows = self.rows();
+                let items: Vec<ListItem> = rows.iter().map(|row| filter::highlight(row, &[], theme.highlight)).collect();
+                let title = match &self.search.live_query {
+                    Some(query) => format!("Results for '{}' ({})", query, rows.len()),
+                    None => "Results".to_string(),
+                };
+                let block = theme.block().title(title);
+                if rows.is_empty() {
+                    let hint = if self.search.live_query.is_some() {
+                        "No matches"
+                    } else {
+                        "Type to search; Up/Down for past searches, Tab to complete"
+                    };
+                    let inner = block.inner(parts[1]);
+                    f.render_widget(block, parts[1]);
+                    f.render_widget(Paragraph::new(hint).style(theme.muted), inner);
+                } else {
+                    self.list_area = Some(self.list.render(f, parts[1], block, items, &theme));
+                }
             }
-            View::Stations => {
-                let items: Vec<ListItem> = self.stations
+            View::Stats => {
+                if let Some(stats) = &self.stats {
+                    let hint = key_hint(&self.keymap, Action::NextPeriod, "change the period");
+                    stats::render(f, main_area, stats, self.stats_period, &hint, &theme);
+                }
+            }
+            View::Map => {
+                let place_of = |id: &String| self.map.station_places.get(id).cloned();
+                let favorites: HashSet<String> = self.favorites.stations.iter().filter_map(|s| place_of(&s.id)).collect();
+                let playing = self.now_playing.station.as_ref().and_then(|s| place_of(&s.id));
+                let areas = self.map.render(f, main_area, &favorites, playing.as_deref(), main_focused, &theme);
+                self.map_areas = Some(areas);
+            }
+            View::Results | View::Stations | View::Favorites | View::FavoriteCountries | View::History => {
+                let rows = self.rows();
+                let visible = self.filter.apply(&rows);
+                let items: Vec<ListItem> = visible
                     .iter()
-                    .map(|station| {
-                        let is_favorite = station.page.url.split('/').last()
-                            .map(|id| self.is_station_favorite(id))
-                            .unwrap_or(false);
-                        let icon = if is_favorite { "⭐📻" } else { "📻" };
-                        ListItem::new(format!("{} {}", icon, station.title))
-                    })
+                    .map(|v| filter::highlight(&rows[v.index], &v.positions, theme.highlight))
                     .collect();
 
-                let list = List::new(items)
-                    .block(Block::default().borders(Borders::ALL).title("Radio Stations"))
-                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
-                f.render_stateful_widget(list, chunks[1], &mut self.list_state);
+                let title = match self.current_view {
+                    View::Results => "Search Results",
+                    View::Stations => "Radio Stations",
+                    View::Favorites => "All Favorites",
+                    View::FavoriteCountries => "Favorite Countries",
+                    _ => "Recently Played",
+                };
+                let title = if self.filter.is_active() || self.mode == Mode::Insert {
+                    format!("{} ({}/{}) /{}", title, visible.len(), rows.len(), self.filter.query)
+                } else {
+                    title.to_string()
+                };
+                let block = theme.pane(main_focused).title(title);
+                self.list_area = Some(self.list.render(f, main_area, block, items, &theme));
             }
-            View::Favorites => {
-                let mut items: Vec<ListItem> = Vec::new();
-                
-                // Add favorite countries first
-                for country in &self.favorites.countries {
-                    items.push(ListItem::new(format!("⭐🌍 {}", country.title)));
-                }
-                
-                // Add favorite stations
-                for station in &self.favorites.stations {
-                    let country = station.country.as_deref().unwrap_or("Unknown");
-                    items.push(ListItem::new(format!("⭐📻 {} ({})", station.title, country)));
+        }
+
+        // Now playing
+        let sleep_in = self.sleep_at.map(|at| at.saturating_duration_since(Instant::now()));
+        let panel = self.now_playing.render(
+            f,
+            chunks[2],
+            self.visualizer.style().label(),
+            sleep_in,
+            panel_focused,
+            &theme,
+        );
+        self.clickable.extend(panel.buttons);
+        if let Some(area) = panel.visualizer {
+            match self.playback.tap() {
+                Some(tap) => {
+                    self.visualizer.update(&tap);
+                    self.visualizer.render(f, area, &theme);
+                }
+                None => {
+                    let note = Paragraph::new("Playing through termadio daemon - visualizer unavailable")
+                        .style(theme.muted);
+                    f.render_widget(note, area);
                 }
+            }
+        }
 
-                let list = List::new(items)
-                    .block(Block::default().borders(Borders::ALL).title("All Favorites"))
-                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
-                f.render_stateful_widget(list, chunks[1], &mut self.list_state);
+        // Status bar, or the command line while the palette is open
+        if let Some(palette) = &self.palette {
+            self.render_palette(f, palette, chunks[3]);
+            if self.show_help {
+                render_help(f, &self.keymap, &theme);
             }
-            View::FavoriteCountries => {
-                let items: Vec<ListItem> = self.favorites.countries
-                    .iter()
-                    .map(|country| ListItem::new(format!("⭐🌍 {}", country.title)))
-                    .collect();
+            return;
+        }
+        let status_text = match &self.loading {
+            Some(loading) => {
+                let spinner = theme.icons.spinner;
+                let frame = (loading.started.elapsed().as_millis() / 80) as usize % spinner.len();
+                format!("{} {}... (Esc to cancel)", spinner[frame], loading.label)
+            }
+            None => self.status_message.clone(),
+        };
+        let badge = match self.mode {
+            Mode::Normal => theme.normal_badge,
+            Mode::Insert => theme.insert_badge,
+        };
+        let mut spans = vec![Span::styled(format!(" {} ", self.mode.label()), badge), Span::raw(" ")];
+        if let Some(count) = self.list.count() {
+            spans.push(Span::styled(format!("{} ", count), theme.highlight));
+        }
+        if self.jumping {
+            spans.push(Span::styled("jump to: ", theme.highlight));
+        }
+        spans.push(Span::styled(status_text, theme.status));
+        let status = Paragraph::new(Line::from(spans))
+        .block(theme.block());
+        f.render_widget(status, chunks[3]);
+
+        if self.show_help {
+            render_help(f, &self.keymap, &theme);
+        }
+    }
+
+    /// Favorite countries and stations, with the playing station marked.
+    fn render_sidebar(&mut self, f: &mut Frame, area: Rect, focused: bool) {
+        let theme = self.theme;
+        let block = theme.pane(focused).title("Favorites");
+        if self.favorites.countries.is_empty() && self.favorites.stations.is_empty() {
+            let hint = Paragraph::new("No favorites yet").style(theme.muted).block(block);
+            f.render_widget(hint, area);
+            return;
+        }
+        let playing = self.now_playing.station.as_ref().map(|station| station.id.as_str());
+        let countries = self
+            .favorites
+            .countries
+            .iter()
+            .map(|country| ListItem::new(format!("{} {}", theme.icons.country, country.display_name())));
+        let stations = self.favorites.stations.iter().map(|station| {
+            let item = ListItem::new(format!("{} {}", theme.icons.station, station.display_name()));
+            if playing == Some(station.id.as_str()) {
+                item.style(theme.accent.add_modifier(Modifier::BOLD))
+            } else {
+                item
+            }
+        });
+        let items = countries.chain(stations).collect();
+        self.sidebar_area = Some(self.sidebar.render(f, area, block, items, &theme));
+    }
 
-                let list = List::new(items)
-                    .block(Block::default().borders(Borders::ALL).title("Favorite Countries"))
-                    .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
-                f.render_stateful_widget(list, chunks[1], &mut self.list_state);
+    /// "Favorites › Morocco › Stations", at most `width` characters.
+    fn breadcrumbs(&self, width: usize) -> String {
+        let screens = self
+            .navigation
+            .trail()
+            .map(|screen| (screen.view, screen.label.as_str()))
+            .chain(std::iter::once((self.current_view, self.view_label.as_str())));
+        let mut crumbs = Vec::new();
+        for (view, label) in screens {
+            match view {
+                View::Results => crumbs.push(format!("\"{}\"", label)),
+                View::Stations => {
+                    crumbs.push(label.to_string());
+                    crumbs.push("Stations".to_string());
+                }
+                _ => crumbs.push(label.to_string()),
+            }
+        }
+        let separator = format!(" {} ", self.theme.icons.crumb);
+        let mut skipped = 0;
+        loop {
+            let shown = &crumbs[skipped..];
+            let trail = if skipped > 0 {
+                format!("...{}{}", separator, shown.join(&separator))
+            } else {
+                shown.join(&separator)
+            };
+            if trail.chars().count() <= width || shown.len() <= 1 {
+                return trail;
             }
+            skipped += 1;
+        }
+    }
+
+    /// The `:` line in place of the status bar, with completions above it.
+    fn render_palette(&self, f: &mut Frame, palette: &Palette, area: Rect) {
+        let theme = &self.theme;
+        let mut spans = vec![
+            Span::styled(" : ", theme.command_badge),
+            Span::raw(" "),
+            Span::raw(palette.input.clone()),
+            Span::styled("_", theme.highlight),
+        ];
+        if let Some(error) = &palette.error {
+            spans.push(Span::styled(format!("  {}", error), theme.bad));
         }
+        let line = Paragraph::new(Line::from(spans))
+        .block(theme.block().title("Command (Tab completes, Enter runs, Esc closes)"));
+        f.render_widget(line, area);
 
-        // Status bar
-        let status = Paragraph::new(self.status_message.as_str())
-            .style(Style::default().fg(Color::Green))
-            .block(Block::default().borders(Borders::ALL));
-        f.render_widget(status, chunks[2]);
+        let completions = palette.completions(&self.candidates());
+        if completions.is_empty() {
+            return;
+        }
+        let shown = completions.len().min(MAX_COMPLETIONS);
+        let height = (shown as u16 + 2).min(area.y);
+        let width = area.width.min(60);
+        let popup = Rect::new(area.x, area.y - height, width, height);
+        let items: Vec<ListItem> = completions
+            .iter()
+            .take(shown)
+            .map(|completion| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!(":{}", completion.text), theme.highlight),
+                    Span::styled(format!("  {}", completion.hint), theme.muted),
+                ]))
+            })
+            .collect();
+        f.render_widget(Clear, popup);
+        f.render_widget(List::new(items).block(theme.block()), popup);
     }
-}
\ No newline at end of file
+}
+
+/// The `?` popup: every bound action with its keys, then the palette commands.
+fn render_help(f: &mut Frame, keymap: &Keymap, theme: &Theme) {
+    let heading = theme.accent.add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::from(Span::styled("Keys (NORMAL mode)", heading))];
+    for action in Action::value_variants() {
+        let keys = keymap.keys(*action);
+        if keys.is_empty() {
+            continue;
+        }
+        let keys: Vec<String> = keys.iter().map(|key| key.to_string()).collect();
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {:<18}", keys.join(", ")), theme.highlight),
+            Span::raw(action.description()),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("Commands (every action also works as :name)", heading)));
+    for (name, hint) in COMMANDS {
+        lines.push(Line::from(Span::styled(format!("  :{} {}", name, hint), theme.highlight)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "In INSERT mode keys type into the search box or filter; Esc leaves it.",
+        theme.muted,
+    )));
+
+    let screen = f.size();
+    let width = screen.width.min(76);
+    let height = screen.height.min(lines.len() as u16 + 2);
+    let area = Rect::new(
+        screen.x + (screen.width - width) / 2,
+        screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(
+        Paragraph::new(lines).block(theme.block().title("Help (any key closes)")),
+        area,
+    );
+}
+
+/// What follows a favorite's name in the lists: `details` in parentheses,
+/// then the note.
+fn favorite_suffix(details: &[&str], note: Option<&str>, dot: &str) -> String {
+    let mut suffix = String::new();
+    if !details.is_empty() {
+        suffix.push_str(&format!(" ({})", details.join(", ")));
+    }
+    if let Some(note) = note {
+        suffix.push_str(&format!(" {} {}", dot, note));
+    }
+    suffix
+}
+
+/// "Controls: s=search, ..." from the first key bound to each hinted action.
+fn controls_hint(keymap: &Keymap) -> String {
+    let controls: Vec<String> = HINTED_ACTIONS
+        .iter()
+        .filter_map(|&action| Some(format!("{}={}", keymap.keys(action).first()?, action.name())))
+        .collect();
+    format!("Controls: {}", controls.join(", "))
+}
+
+/// "press 'a' to favorite" with the first key bound to `action`, or its
+/// palette command when it has no key.
+fn key_hint(keymap: &Keymap, action: Action, what: &str) -> String {
+    match keymap.keys(action).first() {
+        Some(key) => format!("press '{}' to {}", key, what),
+        None => format!("type :{} to {}", action.name(), what),
+    }
+}
+
+/// The stations listed on a country's or place's page.
+fn page_stations(page: CountryPage) -> Vec<Station> {
+    page.data
+        .content
+        .into_iter()
+        .flat_map(|item| item.items.unwrap_or_default())
+        .map(|item| Station {
+            title: item.page.title,
+            page: Page { url: item.page.url },
+        })
+        .collect()
+}
@@ -0,0 +1,164 @@
+//! Back/forward history of the views visited. Each screen is kept as it was
+//! left: its selection, scroll offset, filter and the data it showed.
+
+use ratatui::widgets::ListState;
+
+use super::View;
+use crate::api::{Hit, Station};
+use crate::history::HistoryEntry;
+use crate::stats::Stats;
+
+/// Most screens kept to go back to; the oldest are forgotten.
+const LIMIT: usize = 50;
+
+pub struct Screen {
+    pub view: View,
+    /// Shown in the breadcrumb trail: the query for results, the country for stations.
+    pub label: String,
+    pub list_state: ListState,
+    pub filter: String,
+    pub data: ScreenData,
+}
+
+/// The data a view was showing, for the views that load their own.
+pub enum ScreenData {
+    None,
+    Results(Vec<Hit>),
+    Stations(Vec<Station>),
+    History(Vec<HistoryEntry>),
+    Stats(Box<Stats>),
+}
+
+#[derive(Default)]
+pub struct Navigation {
+    back: Vec<Screen>,
+    forward: Vec<Screen>,
+}
+
+impl Navigation {
+    /// Leaves `current` for a new screen, which drops the forward history.
+    pub fn visit(&mut self, current: Screen) {
+        self.back.push(current);
+        if self.back.len() > LIMIT {
+            self.back.remove(0);
+        }
+        self.forward.clear();
+    }
+
+    /// The screen before `current`, or `current` itself at the start.
+    pub fn back(&mut self, current: Screen) -> Screen {
+        match self.back.pop() {
+            Some(previous) => {
+                self.forward.push(current);
+                previous
+            }
+            None => current,
+        }
+    }
+
+    /// The screen `back` left, or `current` itself when there is none.
+    pub fn forward(&mut self, current: Screen) -> Screen {
+        match self.forward.pop() {
+            Some(next) => {
+                self.back.push(current);
+                next
+            }
+            None => current,
+        }
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    /// The screens before the current one, oldest first.
+    pub fn trail(&self) -> impl Iterator<Item = &Screen> {
+        self.back.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(label: &str) -> Screen {
+        Screen {
+            view: View::Results,
+            label: label.to_string(),
+            list_state: ListState::default(),
+            filter: String::new(),
+            data: ScreenData::None,
+        }
+    }
+
+    fn labels(navigation: &Navigation) -> Vec<&str> {
+        navigation.trail().map(|screen| screen.label.as_str()).collect()
+    }
+
+    #[test]
+    fn goes_back_and_forward() {
+        let mut navigation = Navigation::default();
+        navigation.visit(screen("a"));
+        navigation.visit(screen("b"));
+        assert_eq!(labels(&navigation), ["a", "b"]);
+
+        let current = navigation.back(screen("c"));
+        assert_eq!(current.label, "b");
+        assert!(navigation.can_go_forward());
+        let current = navigation.back(current);
+        assert_eq!(current.label, "a");
+        assert!(!navigation.can_go_back());
+        // At the start, back stays put.
+        let current = navigation.back(current);
+        assert_eq!(current.label, "a");
+
+        let current = navigation.forward(current);
+        assert_eq!(current.label, "b");
+        let current = navigation.forward(current);
+        assert_eq!(current.label, "c");
+        assert!(!navigation.can_go_forward());
+        let current = navigation.forward(current);
+        assert_eq!(current.label, "c");
+        assert_eq!(labels(&navigation), ["a", "b"]);
+    }
+
+    #[test]
+    fn keeps_screens_as_they_were_left() {
+        let mut navigation = Navigation::default();
+        let mut left = screen("a");
+        left.list_state.select(Some(3));
+        left.filter = "jazz".to_string();
+        navigation.visit(left);
+        let current = navigation.back(screen("b"));
+        assert_eq!(current.list_state.selected(), Some(3));
+        assert_eq!(current.filter, "jazz");
+    }
+
+    #[test]
+    fn visiting_after_going_back_drops_the_forward_history() {
+        let mut navigation = Navigation::default();
+        navigation.visit(screen("a"));
+        navigation.visit(screen("b"));
+        let current = navigation.back(screen("c"));
+        navigation.visit(current);
+        assert!(!navigation.can_go_forward());
+        assert_eq!(labels(&navigation), ["a", "b"]);
+        let current = navigation.forward(screen("d"));
+        assert_eq!(current.label, "d");
+    }
+
+    #[test]
+    fn forgets_the_oldest_screens() {
+        let mut navigation = Navigation::default();
+        for i in 0..LIMIT + 2 {
+            navigation.visit(screen(&i.to_string()));
+        }
+        let labels = labels(&navigation);
+        assert_eq!(labels.len(), LIMIT);
+        assert_eq!(labels[0], "2");
+    }
+}
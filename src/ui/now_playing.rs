@@ -0,0 +1,271 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+use std::time::{Duration, Instant};
+
+use super::keymap::Action;
+use super::theme::{Icons, Theme};
+use crate::player::{PlaybackState, PlayerEvent, PlayerStatus, StationInfo};
+
+/// Width of the info column next to the visualizer.
+const INFO_WIDTH: u16 = 46;
+/// Chunks at which the buffer gauge is full.
+const BUFFER_GAUGE: usize = 4;
+/// Clickable buttons under the details, with what they do.
+const BUTTONS: [(&str, Action); 4] = [
+    ("[Play/Pause]", Action::PlayPause),
+    ("[Stop]", Action::Stop),
+    ("[Vol -]", Action::VolumeDown),
+    ("[Vol +]", Action::VolumeUp),
+];
+
+/// Where the panel drew what, for mouse handling.
+#[derive(Default)]
+pub struct PanelAreas {
+    pub visualizer: Option<Rect>,
+    pub buttons: Vec<(Rect, Action)>,
+}
+
+/// What's playing, kept up to date from player events and periodic status polls.
+pub struct NowPlaying {
+    pub station: Option<StationInfo>,
+    track: Option<String>,
+    state: PlaybackState,
+    volume: u8,
+    buffered: usize,
+    /// Playing time before the last pause.
+    banked: Duration,
+    /// Start of the current stretch of playing, unless paused or stopped.
+    since: Option<Instant>,
+    /// Last poll's byte count and time, for the receive rate.
+    received: Option<(u64, Instant)>,
+    /// Incoming stream bitrate in kbps.
+    rate: Option<u64>,
+}
+
+impl NowPlaying {
+    pub fn new(status: Option<PlayerStatus>) -> Self {
+        let mut now_playing = Self {
+            station: None,
+            track: None,
+            state: PlaybackState::Stopped,
+            volume: 100,
+            buffered: 0,
+            banked: Duration::ZERO,
+            since: None,
+            received: None,
+            rate: None,
+        };
+        // When attaching mid-play, time counts from now.
+        if let Some(status) = status {
+            now_playing.sync(status);
+        }
+        now_playing
+    }
+
+    pub fn apply(&mut self, event: &PlayerEvent) {
+        match event {
+            PlayerEvent::Playing { station } => {
+                self.station = Some(station.clone());
+                self.track = None;
+                self.state = PlaybackState::Playing;
+                self.banked = Duration::ZERO;
+                self.since = Some(Instant::now());
+            }
+            PlayerEvent::Paused => {
+                self.state = PlaybackState::Paused;
+                if let Some(since) = self.since.take() {
+                    self.banked += since.elapsed();
+                }
+            }
+            PlayerEvent::Resumed => {
+                self.state = PlaybackState::Playing;
+                self.since.get_or_insert_with(Instant::now);
+            }
+            PlayerEvent::Stopped => {
+                self.station = None;
+                self.track = None;
+                self.state = PlaybackState::Stopped;
+                self.since = None;
+                self.rate = None;
+            }
+            PlayerEvent::VolumeChanged { volume } => self.volume = *volume,
+            PlayerEvent::TrackChanged { title } => self.track = Some(title.clone()),
+            PlayerEvent::DeadAir { .. } | PlayerEvent::FellBack { .. } | PlayerEvent::Unavailable { .. } => {}
+        }
+    }
+
+    /// Takes in a fresh status, correcting anything an event may have missed.
+    pub fn sync(&mut self, status: PlayerStatus) {
+        let now = Instant::now();
+        if self.station.as_ref().map(|s| &s.id) != status.station.as_ref().map(|s| &s.id) {
+            self.banked = Duration::ZERO;
+            self.since = None;
+        }
+        match status.state {
+            PlaybackState::Playing => {
+                self.since.get_or_insert(now);
+            }
+            PlaybackState::Paused => {
+                if let Some(since) = self.since.take() {
+                    self.banked += now.duration_since(since);
+                }
+            }
+            PlaybackState::Stopped => self.since = None,
+        }
+        self.station = status.station;
+        self.track = status.track;
+        self.state = status.state;
+        self.volume = status.volume;
+        self.buffered = status.buffered;
+
+        if let Some((received, at)) = self.received {
+            let secs = now.duration_since(at).as_secs_f64();
+            if secs > 0.0 && status.received >= received {
+                self.rate = Some(((status.received - received) as f64 * 8.0 / 1000.0 / secs).round() as u64);
+            }
+        }
+        self.received = Some((status.received, now));
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.banked + self.since.map(|since| since.elapsed()).unwrap_or_default()
+    }
+
+    /// Rows the panel needs: a single line when nothing is playing.
+    pub fn height(&self) -> u16 {
+        if self.station.is_some() { 12 } else { 3 }
+    }
+
+    /// Draws the panel, leaving the visualizer's area for the caller to fill.
+    pub fn render(
+        &self,
+        f: &mut Frame,
+        area: Rect,
+        visualizer_label: &str,
+        sleep_in: Option<Duration>,
+        focused: bool,
+        theme: &Theme,
+    ) -> PanelAreas {
+        let icons = theme.icons;
+        let Some(station) = &self.station else {
+            let idle = Paragraph::new(format!("{} Nothing playing", icons.stopped))
+                .style(theme.muted)
+                .block(theme.pane(focused).title("Now Playing"));
+            f.render_widget(idle, area);
+            return PanelAreas::default();
+        };
+
+        let sleep = sleep_in
+            .map(|left| format!(" {} {} {}", icons.dot, icons.sleep, format_elapsed(left)))
+            .unwrap_or_default();
+        let block = theme
+            .pane(focused)
+            .title(format!("Now Playing ({}){}", visualizer_label, sleep));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(INFO_WIDTH), Constraint::Min(0)])
+            .split(inner);
+
+        let (state, state_style) = match self.state {
+            PlaybackState::Playing => (format!("{} Playing", icons.playing), theme.good),
+            PlaybackState::Paused => (format!("{} Paused", icons.paused), theme.warn),
+            PlaybackState::Stopped => (format!("{} Stopped", icons.stopped), theme.muted),
+        };
+        let label = theme.muted;
+        let lines = vec![
+            Line::from(Span::styled(station.title.clone(), theme.accent.add_modifier(Modifier::BOLD))),
+            Line::from(vec![
+                Span::styled("Country  ", label),
+                Span::raw(station.country.clone().unwrap_or_else(|| "Unknown".to_string())),
+            ]),
+            Line::from(vec![
+                Span::styled("Track    ", label),
+                Span::raw(self.track.clone().map(|t| format!("{} {}", icons.track, t)).unwrap_or_else(|| "-".to_string())),
+            ]),
+            Line::from(vec![
+                Span::styled("State    ", label),
+                Span::styled(state, state_style),
+                Span::raw(format!("  {}", format_elapsed(self.elapsed()))),
+            ]),
+            Line::from(vec![
+                Span::styled("Volume   ", label),
+                Span::raw(format!("{} {}%", gauge(icons, self.volume as usize, 100, 10), self.volume)),
+            ]),
+            self.buffer_line(theme),
+        ];
+        let details = columns[0];
+        f.render_widget(Paragraph::new(lines), details);
+
+        // One line below the details, if the panel is tall enough.
+        let mut buttons = Vec::new();
+        let y = details.y + 7;
+        if y < details.bottom() {
+            let mut x = details.x;
+            let mut spans = Vec::new();
+            for (label, action) in BUTTONS {
+                let width = (label.len() as u16).min(details.right().saturating_sub(x));
+                buttons.push((Rect::new(x, y, width, 1), action));
+                spans.push(Span::styled(label, theme.accent));
+                spans.push(Span::raw(" "));
+                x += label.len() as u16 + 1;
+            }
+            f.render_widget(Paragraph::new(Line::from(spans)), Rect::new(details.x, y, details.width, 1));
+        }
+
+        PanelAreas {
+            visualizer: Some(columns[1]),
+            buttons,
+        }
+    }
+
+    fn buffer_line(&self, theme: &Theme) -> Line<'static> {
+        let (health, style): (_, Style) = match self.buffered {
+            0 => ("empty", theme.bad),
+            1 => ("low", theme.warn),
+            _ => ("good", theme.good),
+        };
+        let rate = self
+            .rate
+            .map(|kbps| format!(" {} {} kbps", theme.icons.dot, kbps))
+            .unwrap_or_default();
+        Line::from(vec![
+            Span::styled("Buffer   ", theme.muted),
+            Span::styled(
+                format!("{} {}", gauge(theme.icons, self.buffered.min(BUFFER_GAUGE), BUFFER_GAUGE, 4), health),
+                style,
+            ),
+            Span::raw(rate),
+        ])
+    }
+}
+
+/// "▮▮▮▯▯"-style gauge of `value` out of `max`, `width` cells wide.
+fn gauge(icons: &Icons, value: usize, max: usize, width: usize) -> String {
+    let filled = (value * width + max / 2) / max.max(1);
+    format!(
+        "{}{}",
+        icons.gauge_full.repeat(filled.min(width)),
+        icons.gauge_empty.repeat(width - filled.min(width))
+    )
+}
+
+/// Elapsed time as "12:34", or with hours past an hour ("1:02:03").
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
@@ -0,0 +1,225 @@
+//! The `:` command palette: keymap actions by name plus a few commands that
+//! take an argument, like `:volume 50` or `:country Morocco`.
+
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use std::time::Duration;
+
+use super::keymap::Action;
+use crate::history::parse_period;
+
+/// Commands besides the actions, with a hint for their argument.
+pub const COMMANDS: [(&str, &str); 7] = [
+    ("play", "<station id>"),
+    ("country", "<name>"),
+    ("volume", "<0-100>"),
+    ("sleep", "<30m, 1h...|off>"),
+    ("export", "m3u"),
+    ("rename", "<name>, or nothing for the original title"),
+    ("note", "<text>, or nothing to clear it"),
+];
+/// Most completions listed under the palette.
+pub const MAX_COMPLETIONS: usize = 8;
+
+pub enum Command {
+    Action(Action),
+    Play(String),
+    Country(String),
+    Volume(u8),
+    /// Stop playback after a while, or cancel the timer with `None`.
+    Sleep(Option<Duration>),
+    ExportM3u,
+    /// Name the selected favorite, or show its own title again with `None`.
+    Rename(Option<String>),
+    /// Set or clear the note on the selected favorite.
+    Note(Option<String>),
+}
+
+/// A full palette line to complete to, with a note shown next to it.
+pub struct Completion {
+    pub text: String,
+    pub hint: String,
+}
+
+/// What the palette can complete arguments from.
+pub struct Candidates {
+    /// Station IDs and titles.
+    pub stations: Vec<(String, String)>,
+    pub countries: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Palette {
+    pub input: String,
+    /// Why the input didn't run, shown until it's edited.
+    pub error: Option<String>,
+}
+
+impl Palette {
+    pub fn parse(&self) -> Result<Command> {
+        let input = self.input.trim();
+        let (name, argument) = match input.split_once(' ') {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (input, ""),
+        };
+        let require = |what: &str| {
+            if argument.is_empty() {
+                Err(anyhow!("Usage: :{} {}", name, what))
+            } else {
+                Ok(argument.to_string())
+            }
+        };
+        match name {
+            "play" => Ok(Command::Play(require("<station id>")?)),
+            "country" => Ok(Command::Country(require("<name>")?)),
+            "volume" => {
+                let volume: u8 = require("<0-100>")?
+                    .trim_end_matches('%')
+                    .parse()
+                    .map_err(|_| anyhow!("Volume must be a number from 0 to 100"))?;
+                Ok(Command::Volume(volume.min(100)))
+            }
+            "sleep" => match require("<30m, 1h...|off>")?.as_str() {
+                "off" => Ok(Command::Sleep(None)),
+                period => {
+                    Ok(Command::Sleep(Some(parse_period(period)?.to_std()?)))
+                }
+            },
+            "export" => match require("m3u")?.as_str() {
+                "m3u" => Ok(Command::ExportM3u),
+                other => bail!("Unknown export format '{}' (expected m3u)", other),
+            },
+            "rename" => Ok(Command::Rename(Some(argument.to_string()).filter(|a| !a.is_empty()))),
+            "note" => Ok(Command::Note(Some(argument.to_string()).filter(|a| !a.is_empty()))),
+            "" => bail!("Type a command, Tab completes"),
+            _ if argument.is_empty() => Action::from_str(name, true)
+                .map(Command::Action)
+                .map_err(|_| anyhow!("Unknown command '{}'", name)),
+            _ => bail!("'{}' takes no argument", name),
+        }
+    }
+
+    /// Completions for the current input, best first.
+    pub fn completions(&self, candidates: &Candidates) -> Vec<Completion> {
+        let input = self.input.trim_start().to_lowercase();
+        match input.split_once(' ') {
+            None => {
+                let commands = COMMANDS.iter().map(|(name, hint)| (name.to_string(), hint.to_string()));
+                let actions = Action::value_variants()
+                    .iter()
+                    .filter(|action| !matches!(action, Action::Command))
+                    .map(|action| (action.name(), action.description()));
+                commands
+                    .chain(actions)
+                    .filter(|(name, _)| name.starts_with(&input))
+                    .map(|(text, hint)| Completion { text, hint })
+                    .collect()
+            }
+            Some((name, argument)) => {
+                let argument = argument.trim_start();
+                let options: Vec<(String, String)> = match name {
+                    "play" => candidates.stations.clone(),
+                    "country" => candidates.countries.iter().map(|c| (c.clone(), String::new())).collect(),
+                    "sleep" => ["15m", "30m", "1h", "2h", "off"]
+                        .iter()
+                        .map(|option| (option.to_string(), String::new()))
+                        .collect(),
+                    "export" => vec![("m3u".to_string(), "favorites.m3u".to_string())],
+                    _ => Vec::new(),
+                };
+                options
+                    .into_iter()
+                    .filter(|(value, hint)| {
+                        value.to_lowercase().starts_with(argument) || hint.to_lowercase().contains(argument)
+                    })
+                    .map(|(value, hint)| Completion {
+                        text: format!("{} {}", name, value),
+                        hint,
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Tab: completes a single match (and the space before an argument), or
+    /// as much as all matches have in common.
+    pub fn complete(&mut self, candidates: &Candidates) {
+        let completions = self.completions(candidates);
+        match completions.as_slice() {
+            [] => {}
+            [only] => {
+                self.input = only.text.clone();
+                if COMMANDS.iter().any(|(name, _)| *name == only.text) {
+                    self.input.push(' ');
+                }
+            }
+            [first, rest @ ..] => {
+                let common = rest.iter().fold(first.text.clone(), |common, completion| {
+                    common
+                        .chars()
+                        .zip(completion.text.chars())
+                        .take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+                        .map(|(a, _)| a)
+                        .collect()
+                });
+                if common.len() > self.input.len() && common.to_lowercase().starts_with(&self.input.to_lowercase()) {
+                    self.input = common;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<Command> {
+        Palette { input: input.to_string(), ..Palette::default() }.parse()
+    }
+
+    fn error(input: &str) -> String {
+        match parse(input) {
+            Ok(_) => panic!("{:?} parsed", input),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_commands_with_arguments() {
+        assert!(matches!(parse("play  good1 ").unwrap(), Command::Play(id) if id == "good1"));
+        assert!(matches!(parse("country Costa Rica").unwrap(), Command::Country(name) if name == "Costa Rica"));
+        assert!(matches!(parse("volume 50%").unwrap(), Command::Volume(50)));
+        assert!(matches!(parse("volume 150").unwrap(), Command::Volume(100)));
+        assert!(matches!(parse("export m3u").unwrap(), Command::ExportM3u));
+        assert!(matches!(parse("rename Jazz").unwrap(), Command::Rename(Some(name)) if name == "Jazz"));
+        assert!(matches!(parse("rename").unwrap(), Command::Rename(None)));
+        assert!(matches!(parse("note  ").unwrap(), Command::Note(None)));
+    }
+
+    #[test]
+    fn parses_actions_by_name() {
+        assert!(matches!(parse("quit").unwrap(), Command::Action(Action::Quit)));
+        assert!(matches!(parse("toggle-favorite").unwrap(), Command::Action(Action::ToggleFavorite)));
+        assert_eq!(error("quit now"), "'quit' takes no argument");
+        assert_eq!(error("launch"), "Unknown command 'launch'");
+        assert_eq!(error(""), "Type a command, Tab completes");
+    }
+
+    #[test]
+    fn parses_sleep_times() {
+        assert!(matches!(parse("sleep 30m").unwrap(), Command::Sleep(Some(d)) if d == Duration::from_secs(1800)));
+        assert!(matches!(parse("sleep 2h").unwrap(), Command::Sleep(Some(d)) if d == Duration::from_secs(7200)));
+        assert!(matches!(parse("sleep off").unwrap(), Command::Sleep(None)));
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(error("play"), "Usage: :play <station id>");
+        assert_eq!(error("volume loud"), "Volume must be a number from 0 to 100");
+        assert!(error("export pls").starts_with("Unknown export format"));
+        assert!(error("sleep 5x").starts_with("Invalid period '5x'"));
+        assert!(error("sleep 99999999999w").starts_with("Invalid period"));
+        assert_eq!(error("sleep -5m"), "Invalid period '-5m' (it must be longer than zero)");
+    }
+}
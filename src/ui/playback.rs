@@ -0,0 +1,134 @@
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+
+use crate::daemon::client::DaemonClient;
+use crate::player::tap::SampleTap;
+use crate::player::{AudioPlayer, PlaybackState, PlayerEvent, PlayerStatus, StationInfo};
+
+/// Where the TUI sends playback commands: its own player, or a running daemon.
+/// Cheap to clone, so commands can run in tasks of their own.
+#[derive(Clone)]
+pub enum Playback {
+    Local(Arc<AudioPlayer>),
+    Daemon(DaemonClient),
+}
+
+impl Playback {
+    pub async fn play(&self, station: StationInfo) -> Result<()> {
+        match self {
+            Playback::Local(player) => player.play_station(station),
+            Playback::Daemon(daemon) => blocking(daemon, move |daemon| daemon.play(station)).await,
+        }
+    }
+
+    /// Pauses if playing, resumes otherwise. Returns whether it's now paused.
+    pub async fn toggle_pause(&self) -> Result<bool> {
+        match self {
+            Playback::Local(player) => {
+                let paused = !player.is_paused();
+                if paused {
+                    player.pause();
+                } else {
+                    player.resume();
+                }
+                Ok(paused)
+            }
+            Playback::Daemon(daemon) => {
+                blocking(daemon, |daemon| {
+                    let paused = daemon.status()?.state != PlaybackState::Paused;
+                    if paused {
+                        daemon.pause()?;
+                    } else {
+                        daemon.resume()?;
+                    }
+                    Ok(paused)
+                })
+                .await
+            }
+        }
+    }
+
+    pub async fn stop(&self) -> Result<()> {
+        match self {
+            Playback::Local(player) => {
+                player.stop();
+                Ok(())
+            }
+            Playback::Daemon(daemon) => blocking(daemon, |daemon| daemon.stop()).await,
+        }
+    }
+
+    /// Sets the volume in percent, 0-100.
+    pub async fn set_volume(&self, volume: u8) -> Result<()> {
+        match self {
+            Playback::Local(player) => {
+                player.set_volume(volume);
+                Ok(())
+            }
+            Playback::Daemon(daemon) => blocking(daemon, move |daemon| daemon.set_volume(volume)).await,
+        }
+    }
+
+    pub async fn status(&self) -> Result<PlayerStatus> {
+        match self {
+            Playback::Local(player) => Ok(player.status()),
+            Playback::Daemon(daemon) => blocking(daemon, |daemon| daemon.status()).await,
+        }
+    }
+
+    /// Player events, including changes made by other clients or the player itself.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<PlayerEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        match self {
+            Playback::Local(player) => {
+                let mut events = player.subscribe();
+                tokio::spawn(async move {
+                    loop {
+                        match events.recv().await {
+                            Ok(event) => {
+                                if tx.send(event).is_err() {
+                                    return;
+                                }
+                            }
+                            Err(RecvError::Lagged(_)) => continue,
+                            Err(RecvError::Closed) => return,
+                        }
+                    }
+                });
+            }
+            Playback::Daemon(daemon) => {
+                let daemon = daemon.clone();
+                // The daemon client is blocking, so follow it on a thread of its own.
+                // Without events the UI still works; it just won't notice outside changes.
+                std::thread::spawn(move || {
+                    let Ok((_, events)) = daemon.subscribe() else { return };
+                    for event in events.map_while(Result::ok) {
+                        if tx.send(event).is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        }
+        rx
+    }
+
+    /// Samples are only available when audio is decoded in this process.
+    pub fn tap(&self) -> Option<Arc<SampleTap>> {
+        match self {
+            Playback::Local(player) => Some(player.tap()),
+            Playback::Daemon(_) => None,
+        }
+    }
+}
+
+/// Runs `call` on the blocking pool, since the daemon client waits on its socket.
+async fn blocking<T: Send + 'static>(
+    daemon: &DaemonClient,
+    call: impl FnOnce(&DaemonClient) -> Result<T> + Send + 'static,
+) -> Result<T> {
+    let daemon = daemon.clone();
+    tokio::task::spawn_blocking(move || call(&daemon)).await?
+}
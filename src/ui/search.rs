@@ -0,0 +1,222 @@
+//! The search box: searching as you type once typing pauses, stepping
+//! through past searches with Up/Down, and completing from them and from
+//! favorite countries.
+
+use std::time::{Duration, Instant};
+
+use crate::search_history::SearchHistory;
+
+/// How long typing has to pause before the query is searched.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// Shorter queries aren't searched until Enter.
+const MIN_CHARS: usize = 2;
+
+#[derive(Default)]
+pub struct SearchBox {
+    pub input: String,
+    pub history: SearchHistory,
+    /// The query the results under the input are for.
+    pub live_query: Option<String>,
+    /// Position in the history while stepping through it, and the input
+    /// from before, to come back to.
+    browsing: Option<(usize, String)>,
+    /// When the input gets searched, unless typing goes on.
+    due: Option<Instant>,
+}
+
+impl SearchBox {
+    pub fn new() -> Self {
+        Self {
+            history: SearchHistory::load().unwrap_or_default(),
+            ..Self::default()
+        }
+    }
+
+    /// Call after typing into the input, to search it once typing pauses.
+    pub fn edited(&mut self) {
+        self.browsing = None;
+        self.schedule();
+    }
+
+    fn schedule(&mut self) {
+        self.live_query = None;
+        self.due = (self.input.trim().chars().count() >= MIN_CHARS).then(|| Instant::now() + DEBOUNCE);
+    }
+
+    /// Empties the input, dropping any pending search.
+    pub fn clear(&mut self) {
+        self.input.clear();
+        self.live_query = None;
+        self.browsing = None;
+        self.due = None;
+    }
+
+    /// The query to search now that typing has paused, if it's time.
+    pub fn take_due(&mut self, now: Instant) -> Option<String> {
+        if self.due.is_some_and(|due| now >= due) {
+            self.due = None;
+            return Some(self.input.trim().to_string());
+        }
+        None
+    }
+
+    /// The input is about to be searched for good; no need to also search it live.
+    pub fn cancel_due(&mut self) {
+        self.due = None;
+    }
+
+    /// Up: the search before the one shown, starting from the latest.
+    pub fn older(&mut self) {
+        let queries = &self.history.queries;
+        let index = match &self.browsing {
+            Some((index, _)) => index.saturating_sub(1),
+            None if queries.is_empty() => return,
+            None => queries.len() - 1,
+        };
+        let draft = self.browsing.take().map_or_else(|| self.input.clone(), |(_, draft)| draft);
+        self.input = queries[index].clone();
+        self.browsing = Some((index, draft));
+        self.schedule();
+    }
+
+    /// Down: the search after the one shown, and past the latest, what was
+    /// typed before stepping back.
+    pub fn newer(&mut self) {
+        let Some((index, draft)) = self.browsing.take() else { return };
+        if index + 1 < self.history.queries.len() {
+            self.input = self.history.queries[index + 1].clone();
+            self.browsing = Some((index + 1, draft));
+        } else {
+            self.input = draft;
+        }
+        self.schedule();
+    }
+
+    /// Past searches, latest first, then `countries`, that continue what's typed.
+    pub fn completions(&self, countries: &[String]) -> Vec<String> {
+        let typed = self.input.to_lowercase();
+        if typed.trim().is_empty() {
+            return Vec::new();
+        }
+        let mut completions: Vec<String> = Vec::new();
+        for candidate in self.history.queries.iter().rev().chain(countries) {
+            let lower = candidate.to_lowercase();
+            if lower.starts_with(&typed) && lower != typed && !completions.iter().any(|c| c.to_lowercase() == lower) {
+                completions.push(candidate.clone());
+            }
+        }
+        completions
+    }
+
+    /// Tab: takes the first completion. Returns whether there was one.
+    pub fn complete(&mut self, countries: &[String]) -> bool {
+        let Some(completion) = self.completions(countries).into_iter().next() else { return false };
+        self.input = completion;
+        self.edited();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_history::tests::history;
+
+    /// A search box with `queries` searched before, oldest first.
+    fn search_box(test: &str, queries: &[&str]) -> (SearchBox, std::path::PathBuf) {
+        let (mut history, path) = history(test);
+        for query in queries {
+            history.add(query).unwrap();
+        }
+        (SearchBox { history, ..SearchBox::default() }, path)
+    }
+
+    fn typed(search: &mut SearchBox, text: &str) {
+        search.input = text.to_string();
+        search.edited();
+    }
+
+    #[test]
+    fn steps_through_past_searches() {
+        let (mut search, path) = search_box("browse", &["jazz", "rock", "blues"]);
+        typed(&mut search, "draft");
+        search.older();
+        assert_eq!(search.input, "blues");
+        search.older();
+        search.older();
+        assert_eq!(search.input, "jazz");
+        // Stays on the oldest.
+        search.older();
+        assert_eq!(search.input, "jazz");
+        search.newer();
+        assert_eq!(search.input, "rock");
+        search.newer();
+        search.newer();
+        assert_eq!(search.input, "draft");
+        // Past the draft there's nothing newer.
+        search.newer();
+        assert_eq!(search.input, "draft");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn typing_stops_browsing() {
+        let (mut search, path) = search_box("typing", &["jazz", "rock"]);
+        search.older();
+        typed(&mut search, "rock and roll");
+        search.newer();
+        assert_eq!(search.input, "rock and roll");
+        search.older();
+        assert_eq!(search.input, "rock");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn without_history_up_keeps_the_input() {
+        let (mut search, path) = search_box("empty", &[]);
+        typed(&mut search, "draft");
+        search.older();
+        assert_eq!(search.input, "draft");
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn searches_once_typing_pauses() {
+        let mut search = SearchBox::default();
+        typed(&mut search, "ja");
+        let typed_at = Instant::now();
+        assert_eq!(search.take_due(typed_at), None);
+        // More typing starts the wait over.
+        typed(&mut search, "jaz");
+        assert_eq!(search.take_due(typed_at + DEBOUNCE / 2), None);
+        assert_eq!(search.take_due(Instant::now() + DEBOUNCE), Some("jaz".to_string()));
+        assert_eq!(search.take_due(Instant::now() + DEBOUNCE * 2), None);
+    }
+
+    #[test]
+    fn short_or_cancelled_queries_arent_searched() {
+        let later = || Instant::now() + DEBOUNCE * 2;
+        let mut search = SearchBox::default();
+        typed(&mut search, " j ");
+        assert_eq!(search.take_due(later()), None);
+        typed(&mut search, "jazz");
+        search.cancel_due();
+        assert_eq!(search.take_due(later()), None);
+        typed(&mut search, "jazz");
+        search.clear();
+        assert_eq!(search.take_due(later()), None);
+    }
+
+    #[test]
+    fn completes_from_history_then_countries() {
+        let (mut search, path) = search_box("complete", &["jazz fm", "Japan news", "rock"]);
+        let countries = ["Jamaica".to_string(), "japan news".to_string()];
+        typed(&mut search, "ja");
+        assert_eq!(search.completions(&countries), ["Japan news", "jazz fm", "Jamaica"]);
+        assert!(search.complete(&countries));
+        assert_eq!(search.input, "Japan news");
+        typed(&mut search, "Jamaica");
+        assert!(!search.complete(&countries));
+        std::fs::remove_file(path).unwrap();
+    }
+}
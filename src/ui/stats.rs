@@ -0,0 +1,113 @@
+use chrono::Duration;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, List, ListItem, Paragraph, Sparkline},
+    Frame,
+};
+
+use super::theme::Theme;
+use crate::history::format_duration;
+use crate::stats::{Period, Stats, WEEKDAYS};
+
+/// Draws the listening stats dashboard into `area`. `period_hint` tells how
+/// to switch periods.
+pub fn render(f: &mut Frame, area: Rect, stats: &Stats, period: Period, period_hint: &str, theme: &Theme) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Length(9), Constraint::Min(0)])
+        .split(area);
+
+    let summary = Paragraph::new(format!(
+        "Total {} in {} sessions {} {}",
+        minutes(stats.total_minutes),
+        stats.sessions,
+        theme.icons.dot,
+        period_hint
+    ))
+    .style(theme.highlight)
+    .block(theme.block().title(format!("Listening Stats ({})", period.label())));
+    f.render_widget(summary, rows[0]);
+
+    let charts = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(rows[1]);
+
+    // One sparkline column per hour is tiny, so widen each hour to fill the block.
+    let hours_block = theme.block().title("By hour of day (00-23)");
+    let width = (hours_block.inner(charts[0]).width as usize / 24).max(1);
+    let by_hour: Vec<u64> = stats
+        .by_hour
+        .iter()
+        .flat_map(|&total| std::iter::repeat_n(total.max(0) as u64, width))
+        .collect();
+    let hours = Sparkline::default()
+        .block(hours_block)
+        .data(&by_hour)
+        .bar_set(theme.bar_set())
+        .style(theme.accent);
+    f.render_widget(hours, charts[0]);
+
+    let bars: Vec<Bar> = WEEKDAYS
+        .iter()
+        .zip(stats.by_weekday.iter())
+        .map(|(day, &total)| {
+            Bar::default()
+                .label(Line::from(*day))
+                .value(total.max(0) as u64)
+                .text_value(String::new())
+        })
+        .collect();
+    let weekdays = BarChart::default()
+        .block(theme.block().title("By weekday"))
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(3)
+        .bar_gap(1)
+        .bar_set(theme.bar_set())
+        .bar_style(theme.chart);
+    f.render_widget(weekdays, charts[1]);
+
+    let rankings = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Ratio(1, 3),
+            Constraint::Ratio(1, 3),
+            Constraint::Ratio(1, 3),
+        ])
+        .split(rows[2]);
+
+    let stations: Vec<ListItem> = stats
+        .top_stations
+        .iter()
+        .map(|s| ListItem::new(format!("{} {} ({})", theme.icons.station, s.name, minutes(s.minutes))))
+        .collect();
+    f.render_widget(
+        List::new(stations).block(theme.block().title("Top Stations")),
+        rankings[0],
+    );
+
+    let countries: Vec<ListItem> = stats
+        .top_countries
+        .iter()
+        .map(|c| ListItem::new(format!("{} {} ({})", theme.icons.country, c.name, minutes(c.minutes))))
+        .collect();
+    f.render_widget(
+        List::new(countries).block(theme.block().title("Top Countries")),
+        rankings[1],
+    );
+
+    let tracks: Vec<ListItem> = stats
+        .top_tracks
+        .iter()
+        .map(|t| ListItem::new(format!("{} {} ({}{})", theme.icons.track, t.title, t.plays, theme.icons.times)))
+        .collect();
+    f.render_widget(
+        List::new(tracks).block(theme.block().title("Most Heard Tracks")),
+        rankings[2],
+    );
+}
+
+fn minutes(total: i64) -> String {
+    format_duration(Duration::minutes(total))
+}
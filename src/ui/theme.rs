@@ -0,0 +1,330 @@
+//! Colors and icons for the TUI. A built-in theme is picked in the config,
+//! individual colors can be overridden, and `NO_COLOR` turns colors off.
+
+use anyhow::{anyhow, bail, Result};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    symbols,
+    widgets::{Block, BorderType, Borders},
+};
+
+use crate::config::ThemeConfig;
+
+/// Symbols used in lists, the now-playing panel and status messages.
+pub struct Icons {
+    pub logo: &'static str,
+    pub favorite: &'static str,
+    pub country: &'static str,
+    pub station: &'static str,
+    pub unknown: &'static str,
+    pub track: &'static str,
+    pub playing: &'static str,
+    pub paused: &'static str,
+    pub stopped: &'static str,
+    pub removed: &'static str,
+    pub warning: &'static str,
+    pub fallback: &'static str,
+    pub volume: &'static str,
+    pub sleep: &'static str,
+    pub saved: &'static str,
+    pub gauge_full: &'static str,
+    pub gauge_empty: &'static str,
+    /// Between the header tabs.
+    pub divider: &'static str,
+    /// Between breadcrumbs.
+    pub crumb: &'static str,
+    /// Between details in a list item.
+    pub dot: &'static str,
+    /// After a play count.
+    pub times: &'static str,
+    /// Shown before the selected list item; empty when reverse video is enough.
+    pub selection: &'static str,
+    pub spinner: &'static [&'static str],
+}
+
+const EMOJI: Icons = Icons {
+    logo: "🎵 ",
+    favorite: "⭐",
+    country: "🌍",
+    station: "📻",
+    unknown: "❓",
+    track: "♪",
+    playing: "▶",
+    paused: "⏸",
+    stopped: "⏹",
+    removed: "❌",
+    warning: "⚠️",
+    fallback: "🔀",
+    volume: "🔊",
+    sleep: "💤",
+    saved: "📝",
+    gauge_full: "▮",
+    gauge_empty: "▯",
+    divider: "│",
+    crumb: "›",
+    dot: "·",
+    times: "×",
+    selection: "",
+    spinner: &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"],
+};
+
+const ASCII: Icons = Icons {
+    logo: "",
+    favorite: "*",
+    country: "[country]",
+    station: "[station]",
+    unknown: "[?]",
+    track: "~",
+    playing: ">",
+    paused: "||",
+    stopped: "[]",
+    removed: "x",
+    warning: "!",
+    fallback: "->",
+    volume: "vol",
+    sleep: "zz",
+    saved: "+",
+    gauge_full: "#",
+    gauge_empty: "-",
+    divider: "|",
+    crumb: ">",
+    dot: "-",
+    times: "x",
+    selection: "> ",
+    spinner: &["|", "/", "-", "\\"],
+};
+
+const ASCII_BORDER: symbols::border::Set = symbols::border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+const ASCII_BARS: symbols::bar::Set = symbols::bar::Set {
+    full: "#",
+    seven_eighths: "#",
+    three_quarters: "#",
+    five_eighths: "=",
+    half: "=",
+    three_eighths: "=",
+    one_quarter: ".",
+    one_eighth: ".",
+    empty: " ",
+};
+
+const ASCII_LINE: symbols::line::Set = symbols::line::Set {
+    vertical: "|",
+    horizontal: "=",
+    top_right: "+",
+    top_left: "+",
+    bottom_right: "+",
+    bottom_left: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_down: "+",
+    horizontal_up: "+",
+    cross: "+",
+};
+
+#[derive(Clone, Copy)]
+pub struct Theme {
+    /// Header, tabs, titles and the playing station.
+    pub accent: Style,
+    /// Typed input, filter matches and keys.
+    pub highlight: Style,
+    /// The status bar message.
+    pub status: Style,
+    /// Labels, hints and anything secondary.
+    pub muted: Style,
+    pub good: Style,
+    pub warn: Style,
+    pub bad: Style,
+    /// Spectrum and bar charts.
+    pub chart: Style,
+    pub selected: Style,
+    pub active_tab: Style,
+    pub normal_badge: Style,
+    pub insert_badge: Style,
+    pub command_badge: Style,
+    pub icons: &'static Icons,
+    /// ASCII borders and chart symbols along with the ASCII icons.
+    pub ascii: bool,
+}
+
+impl Theme {
+    /// The configured theme; `no_color` (from `NO_COLOR`) wins over it.
+    pub fn from_config(config: &ThemeConfig, no_color: bool) -> Result<Self> {
+        let mut theme = match config.name.as_str() {
+            "dark" => Self::dark(),
+            "light" => Self::light(),
+            "high-contrast" => Self::high_contrast(),
+            "monochrome" => Self::monochrome(),
+            other => bail!(
+                "unknown theme '{}' (expected 'dark', 'light', 'high-contrast' or 'monochrome')",
+                other
+            ),
+        };
+        if no_color {
+            theme = Self::monochrome();
+        } else {
+            for (name, color) in &config.colors {
+                let color: Color = color
+                    .parse()
+                    .map_err(|_| anyhow!("unknown color '{}' for '{}'", color, name))?;
+                *theme.slot(name)? = Style::default().fg(color);
+            }
+        }
+        if config.ascii {
+            theme.icons = &ASCII;
+            theme.ascii = true;
+        }
+        Ok(theme)
+    }
+
+    /// Whether `NO_COLOR` asks for no colors (set and not empty).
+    pub fn no_color_requested() -> bool {
+        std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+    }
+
+    fn slot(&mut self, name: &str) -> Result<&mut Style> {
+        Ok(match name {
+            "accent" => &mut self.accent,
+            "highlight" => &mut self.highlight,
+            "status" => &mut self.status,
+            "muted" => &mut self.muted,
+            "good" => &mut self.good,
+            "warn" => &mut self.warn,
+            "bad" => &mut self.bad,
+            "chart" => &mut self.chart,
+            other => bail!(
+                "unknown theme color '{}' (expected accent, highlight, status, muted, good, warn, bad or chart)",
+                other
+            ),
+        })
+    }
+
+    fn dark() -> Self {
+        let badge = |bg| Style::default().fg(Color::Black).bg(bg).add_modifier(Modifier::BOLD);
+        Self {
+            accent: Style::default().fg(Color::Cyan),
+            highlight: Style::default().fg(Color::Yellow),
+            status: Style::default().fg(Color::Green),
+            muted: Style::default().fg(Color::DarkGray),
+            good: Style::default().fg(Color::Green),
+            warn: Style::default().fg(Color::Yellow),
+            bad: Style::default().fg(Color::Red),
+            chart: Style::default().fg(Color::Magenta),
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            active_tab: badge(Color::Cyan),
+            normal_badge: badge(Color::Blue),
+            insert_badge: badge(Color::Yellow),
+            command_badge: badge(Color::Magenta),
+            icons: &EMOJI,
+            ascii: false,
+        }
+    }
+
+    /// Darker colors that stay readable on a light background.
+    fn light() -> Self {
+        let badge = |bg| Style::default().fg(Color::White).bg(bg).add_modifier(Modifier::BOLD);
+        Self {
+            accent: Style::default().fg(Color::Blue),
+            highlight: Style::default().fg(Color::Magenta),
+            status: Style::default().fg(Color::Indexed(22)),
+            muted: Style::default().fg(Color::Indexed(242)),
+            good: Style::default().fg(Color::Indexed(28)),
+            warn: Style::default().fg(Color::Indexed(130)),
+            bad: Style::default().fg(Color::Red),
+            chart: Style::default().fg(Color::Blue),
+            selected: Style::default().add_modifier(Modifier::REVERSED),
+            active_tab: badge(Color::Blue),
+            normal_badge: badge(Color::Blue),
+            insert_badge: badge(Color::Magenta),
+            command_badge: badge(Color::Indexed(130)),
+            icons: &EMOJI,
+            ascii: false,
+        }
+    }
+
+    /// Bright, bold colors and no dim text.
+    fn high_contrast() -> Self {
+        let bold = |fg| Style::default().fg(fg).add_modifier(Modifier::BOLD);
+        let badge = |bg| Style::default().fg(Color::Black).bg(bg).add_modifier(Modifier::BOLD);
+        Self {
+            accent: bold(Color::LightCyan),
+            highlight: bold(Color::LightYellow),
+            status: bold(Color::White),
+            muted: Style::default().fg(Color::White),
+            good: bold(Color::LightGreen),
+            warn: bold(Color::LightYellow),
+            bad: bold(Color::LightRed),
+            chart: bold(Color::White),
+            selected: badge(Color::LightYellow),
+            active_tab: badge(Color::White),
+            normal_badge: badge(Color::LightCyan),
+            insert_badge: badge(Color::LightYellow),
+            command_badge: badge(Color::LightMagenta),
+            icons: &EMOJI,
+            ascii: false,
+        }
+    }
+
+    /// No colors at all, only bold, underline and reverse video.
+    fn monochrome() -> Self {
+        let plain = Style::default();
+        let reversed = plain.add_modifier(Modifier::REVERSED | Modifier::BOLD);
+        Self {
+            accent: plain.add_modifier(Modifier::BOLD),
+            highlight: plain.add_modifier(Modifier::UNDERLINED),
+            status: plain,
+            muted: plain,
+            good: plain,
+            warn: plain.add_modifier(Modifier::BOLD),
+            bad: plain.add_modifier(Modifier::BOLD),
+            chart: plain,
+            selected: plain.add_modifier(Modifier::REVERSED),
+            active_tab: reversed,
+            normal_badge: reversed,
+            insert_badge: reversed,
+            command_badge: reversed,
+            icons: &EMOJI,
+            ascii: false,
+        }
+    }
+
+    /// A bordered block, with ASCII borders in ASCII mode.
+    pub fn block<'a>(&self) -> Block<'a> {
+        let block = Block::default().borders(Borders::ALL);
+        if self.ascii {
+            block.border_set(ASCII_BORDER)
+        } else {
+            block
+        }
+    }
+
+    /// A pane's block: thick accent borders while it has focus.
+    pub fn pane<'a>(&self, focused: bool) -> Block<'a> {
+        if !focused {
+            return self.block();
+        }
+        let block = self.block().border_style(self.accent);
+        if self.ascii {
+            block
+        } else {
+            block.border_type(BorderType::Thick)
+        }
+    }
+
+    pub fn bar_set(&self) -> symbols::bar::Set {
+        if self.ascii { ASCII_BARS } else { symbols::bar::NINE_LEVELS }
+    }
+
+    pub fn line_set(&self) -> symbols::line::Set {
+        if self.ascii { ASCII_LINE } else { symbols::line::THICK }
+    }
+}
@@ -0,0 +1,217 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Bar, BarChart, BarGroup, LineGauge},
+    Frame,
+};
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use std::sync::Arc;
+
+use super::theme::Theme;
+use crate::player::tap::SampleTap;
+
+const FFT_SIZE: usize = 2048;
+const BANDS: usize = 32;
+const MIN_FREQ: f32 = 40.0;
+const MAX_FREQ: f32 = 16000.0;
+/// Anything quieter than this is drawn as an empty bar / meter.
+const FLOOR_DB: f32 = -60.0;
+/// How much of the previous frame survives when the signal drops.
+const DECAY: f32 = 0.85;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum VisualizerStyle {
+    Spectrum,
+    Meter,
+    Combined,
+}
+
+impl VisualizerStyle {
+    pub fn next(self) -> Self {
+        match self {
+            VisualizerStyle::Spectrum => VisualizerStyle::Meter,
+            VisualizerStyle::Meter => VisualizerStyle::Combined,
+            VisualizerStyle::Combined => VisualizerStyle::Spectrum,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            VisualizerStyle::Spectrum => "spectrum",
+            VisualizerStyle::Meter => "VU meter",
+            VisualizerStyle::Combined => "spectrum + VU",
+        }
+    }
+}
+
+/// Turns the player's sample tap into spectrum bands and stereo RMS levels.
+pub struct Visualizer {
+    style: VisualizerStyle,
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    samples: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    bands: [f32; BANDS],
+    levels: [f32; 2],
+}
+
+impl Visualizer {
+    pub fn new() -> Self {
+        let fft = FftPlanner::new().plan_fft_forward(FFT_SIZE);
+        // Hann window keeps strong bands from smearing into their neighbours.
+        let window = (0..FFT_SIZE)
+            .map(|i| {
+                let phase = 2.0 * std::f32::consts::PI * i as f32 / (FFT_SIZE - 1) as f32;
+                0.5 - 0.5 * phase.cos()
+            })
+            .collect();
+
+        Self {
+            style: VisualizerStyle::Combined,
+            fft,
+            window,
+            samples: Vec::with_capacity(FFT_SIZE * 2),
+            spectrum: vec![Complex::default(); FFT_SIZE],
+            bands: [0.0; BANDS],
+            levels: [0.0; 2],
+        }
+    }
+
+    pub fn style(&self) -> VisualizerStyle {
+        self.style
+    }
+
+    pub fn toggle_style(&mut self) {
+        self.style = self.style.next();
+    }
+
+    pub fn update(&mut self, tap: &SampleTap) {
+        let channels = tap.channels();
+        tap.snapshot(FFT_SIZE * channels, &mut self.samples);
+
+        // Stereo RMS; mono streams drive both meters.
+        let mut sums = [0.0f32; 2];
+        for frame in self.samples.chunks_exact(channels) {
+            sums[0] += frame[0] * frame[0];
+            sums[1] += frame[channels.min(2) - 1] * frame[channels.min(2) - 1];
+        }
+        for (level, sum) in self.levels.iter_mut().zip(sums) {
+            let rms = (sum / FFT_SIZE as f32).sqrt();
+            *level = smooth(*level, normalize_db(20.0 * rms.log10()));
+        }
+
+        for ((bin, frame), weight) in self
+            .spectrum
+            .iter_mut()
+            .zip(self.samples.chunks_exact(channels))
+            .zip(&self.window)
+        {
+            let mono = frame.iter().sum::<f32>() / channels as f32;
+            *bin = Complex::new(mono * weight, 0.0);
+        }
+        self.fft.process(&mut self.spectrum);
+
+        let sample_rate = tap.sample_rate().max(1) as f32;
+        let bin_width = sample_rate / FFT_SIZE as f32;
+        let max_freq = MAX_FREQ.min(sample_rate / 2.0);
+        // A full-scale sine with a Hann window peaks at FFT_SIZE / 4.
+        let reference = FFT_SIZE as f32 / 4.0;
+
+        for (index, band) in self.bands.iter_mut().enumerate() {
+            let low = band_edge(index, max_freq);
+            let high = band_edge(index + 1, max_freq);
+            let first = ((low / bin_width) as usize).max(1);
+            let last = ((high / bin_width) as usize).clamp(first + 1, FFT_SIZE / 2);
+
+            let magnitude = self.spectrum[first..last]
+                .iter()
+                .map(|c| c.norm())
+                .fold(0.0, f32::max);
+            *band = smooth(*band, normalize_db(20.0 * (magnitude / reference).log10()));
+        }
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        match self.style {
+            VisualizerStyle::Spectrum => self.render_spectrum(f, area, theme),
+            VisualizerStyle::Meter => self.render_meter(f, area, theme),
+            VisualizerStyle::Combined => {
+                let chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(0), Constraint::Length(2)])
+                    .split(area);
+                self.render_spectrum(f, chunks[0], theme);
+                self.render_meter(f, chunks[1], theme);
+            }
+        }
+    }
+
+    fn render_spectrum(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let bar_width = (area.width / BANDS as u16).saturating_sub(1).max(1);
+        let bars: Vec<Bar> = self
+            .bands
+            .iter()
+            .map(|band| {
+                Bar::default()
+                    .value((band * 100.0) as u64)
+                    .text_value(String::new())
+            })
+            .collect();
+
+        let chart = BarChart::default()
+            .data(BarGroup::default().bars(&bars))
+            .max(100)
+            .bar_width(bar_width)
+            .bar_gap(1)
+            .bar_set(theme.bar_set())
+            .bar_style(theme.chart);
+        f.render_widget(chart, area);
+    }
+
+    fn render_meter(&self, f: &mut Frame, area: Rect, theme: &Theme) {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Length(1)])
+            .split(area);
+
+        for ((label, level), row) in ["L", "R"].iter().zip(self.levels).zip(rows.iter()) {
+            let style = if level > 0.9 {
+                theme.bad
+            } else if level > 0.75 {
+                theme.warn
+            } else {
+                theme.good
+            };
+            let gauge = LineGauge::default()
+                .ratio(level.clamp(0.0, 1.0) as f64)
+                .label(format!("{} {:>4.0} dB", label, FLOOR_DB * (1.0 - level)))
+                .line_set(theme.line_set())
+                .gauge_style(style);
+            f.render_widget(gauge, *row);
+        }
+    }
+}
+
+impl Default for Visualizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Logarithmically spaced band edges, so each octave gets the same width.
+fn band_edge(index: usize, max_freq: f32) -> f32 {
+    MIN_FREQ * (max_freq / MIN_FREQ).powf(index as f32 / BANDS as f32)
+}
+
+/// Maps a dBFS value onto 0..1, with `FLOOR_DB` and below as 0.
+fn normalize_db(db: f32) -> f32 {
+    if db.is_finite() {
+        ((db - FLOOR_DB) / -FLOOR_DB).clamp(0.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// Rises instantly and falls back gradually, like a real meter needle.
+fn smooth(previous: f32, current: f32) -> f32 {
+    current.max(previous * DECAY)
+}